@@ -0,0 +1,49 @@
+//! A local receiver for trying out webhooks.
+//!
+//! Run with `WEBHOOK_SECRET=<secret> cargo run --example webhook_receiver`, then register
+//! `http://localhost:8090/` as a webhook and ping it. The server only accepts webhooks on local
+//! addresses when it is started with `WEBHOOK_ALLOW_PRIVATE=true`. Every delivery is printed along with
+//! whether its signature matched. Setting `WEBHOOK_FAIL=1` answers with a 500 instead,
+//! which is handy for watching the retries back off in the delivery log.
+
+use std::env;
+
+use actix_web::{App, HttpRequest, HttpResponse, HttpServer, web};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+async fn receive(req: HttpRequest, body: web::Bytes) -> HttpResponse {
+    let header = |name| req.headers().get(name).and_then(|v| v.to_str().ok()).unwrap_or("");
+
+    let secret = env::var("WEBHOOK_SECRET").unwrap_or_default();
+    let mut mac = Hmac::<Sha256>::new_varkey(secret.as_bytes()).expect("hmac accepts keys of any length");
+    mac.input(&body);
+
+    let signature = header("X-Valyou-Signature");
+    let verified = signature.starts_with("sha256=") && hex::decode(&signature[7..])
+        .map(|sig| mac.verify(&sig).is_ok())
+        .unwrap_or(false);
+
+    println!("{} delivery {} (signature {})", header("X-Valyou-Event"), header("X-Valyou-Delivery"),
+             if verified { "ok" } else { "INVALID" });
+    println!("{}\n", String::from_utf8_lossy(&body));
+
+    if !verified {
+        HttpResponse::Unauthorized().finish()
+    } else if env::var("WEBHOOK_FAIL").is_ok() {
+        HttpResponse::InternalServerError().finish()
+    } else {
+        HttpResponse::NoContent().finish()
+    }
+}
+
+#[actix_rt::main]
+async fn main() -> std::io::Result<()> {
+    let address = env::var("WEBHOOK_ADDRESS").unwrap_or_else(|_| "127.0.0.1:8090".into());
+    println!("listening for webhooks on {}", address);
+
+    HttpServer::new(|| App::new().default_service(web::post().to(receive)))
+        .bind(address)?
+        .run()
+        .await
+}
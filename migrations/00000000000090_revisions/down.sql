@@ -0,0 +1,6 @@
+drop trigger record_revision on entries;
+drop table entry_revisions;
+alter table entries drop column private_revisions;
+
+drop function if exists record_revision;
+drop function if exists immutable_revision;
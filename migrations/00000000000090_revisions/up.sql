@@ -0,0 +1,51 @@
+alter table entries
+    add column private_revisions boolean not null default false;
+
+create table entry_revisions
+(
+    revisionid   bigint primary key default id_generator(),
+    entry        bigint    not null references entries on update cascade on delete cascade,
+    created      timestamp not null default now(),
+    content      varchar   not null,
+    significance float
+);
+
+create index entry_revisions_entry on entry_revisions (entry, revisionid);
+
+insert into entry_revisions (entry, created, content, significance)
+select entryid, coalesce(modifiedc, created), content, significance
+from entries;
+
+create or replace function record_revision() returns trigger as
+$$
+begin
+    if tg_op = 'INSERT' then
+        insert into entry_revisions (entry, content, significance)
+        values (new.entryid, new.content, new.significance);
+    elsif new.content != old.content or new.significance is distinct from old.significance then
+        insert into entry_revisions (entry, content, significance)
+        values (new.entryid, new.content, new.significance);
+    end if;
+
+    return new;
+end;
+$$ language plpgsql;
+
+create trigger record_revision
+    after insert or update of content, significance
+    on entries
+    for each row
+execute procedure record_revision();
+
+create or replace function immutable_revision() returns trigger as
+$$
+begin
+    raise check_violation using constraint = 'immutable_revision';
+end;
+$$ language plpgsql;
+
+create trigger immutable_revision
+    before update
+    on entry_revisions
+    for each row
+execute procedure immutable_revision();
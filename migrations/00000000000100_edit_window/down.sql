@@ -0,0 +1,43 @@
+drop view hidden_entries;
+drop view visible_entries;
+drop view full_entries;
+
+create view full_entries as
+    select entryid, author, journal, created, modified, modifiedc, content, significance, hidden,
+           (select string_agg(tag,',')
+            from entry_tags
+            where entry=entryid) as tags
+    from entries;
+
+create view visible_entries as
+    select entryid, author, journal, created, modified, modifiedc, content, significance, tags
+    from full_entries
+    where not hidden;
+
+create view hidden_entries as
+    select entryid, author, journal, created, modified, modifiedc, content, significance, tags
+    from full_entries
+    where hidden;
+
+create or replace function edit_entry() returns trigger as
+$$
+begin
+    select now() into new.modified;
+    if (new.content != old.content) then
+        if ((floor(extract(epoch from (now() - old.created)))) > 86400) then
+            raise check_violation using constraint = 'edit_after_day';
+        end if;
+
+        select now() into new.modifiedc;
+    end if;
+
+    return new;
+end;
+$$ language plpgsql;
+
+drop function if exists edit_time_left;
+drop function if exists edit_deadline;
+
+alter table journals
+    drop constraint edit_window_hours,
+    drop column edit_window;
@@ -0,0 +1,68 @@
+alter table journals
+    add column edit_window int default 24,
+    add constraint edit_window_hours check ( edit_window >= 0 );
+
+-- the moment an entry stops being editable, or null if the journal allows editing forever
+create or replace function edit_deadline(entry_created timestamp, jid bigint) returns timestamp as
+$$
+declare
+    hours int;
+begin
+    select edit_window from journals where journalid = jid into hours;
+
+    if hours is null then
+        return null;
+    end if;
+
+    return entry_created + make_interval(hours => hours);
+end;
+$$ language plpgsql stable;
+
+create or replace function edit_time_left(entry_created timestamp, jid bigint) returns bigint as
+$$
+declare
+    deadline timestamp;
+begin
+    select edit_deadline(entry_created, jid) into deadline;
+
+    if deadline is null then
+        return null;
+    end if;
+
+    return greatest(0, floor(extract(epoch from (deadline - now()))))::bigint;
+end;
+$$ language plpgsql stable;
+
+create or replace function edit_entry() returns trigger as
+$$
+begin
+    select now() into new.modified;
+    if (new.content != old.content) then
+        if (edit_time_left(old.created, old.journal) <= 0) then
+            raise check_violation using constraint = 'edit_window';
+        end if;
+
+        select now() into new.modifiedc;
+    end if;
+
+    return new;
+end;
+$$ language plpgsql;
+
+create or replace view full_entries as
+    select entryid, author, journal, created, modified, modifiedc, content, significance, hidden,
+           (select string_agg(tag,',')
+            from entry_tags
+            where entry=entryid) as tags,
+           edit_time_left(created, journal) as edit_time_left
+    from entries;
+
+create or replace view visible_entries as
+    select entryid, author, journal, created, modified, modifiedc, content, significance, tags, edit_time_left
+    from full_entries
+    where not hidden;
+
+create or replace view hidden_entries as
+    select entryid, author, journal, created, modified, modifiedc, content, significance, tags, edit_time_left
+    from full_entries
+    where hidden;
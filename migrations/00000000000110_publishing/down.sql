@@ -0,0 +1,25 @@
+drop view hidden_entries;
+drop view visible_entries;
+drop view full_entries;
+
+create view full_entries as
+    select entryid, author, journal, created, modified, modifiedc, content, significance, hidden,
+           (select string_agg(tag,',')
+            from entry_tags
+            where entry=entryid) as tags,
+           edit_time_left(created, journal) as edit_time_left
+    from entries;
+
+create view visible_entries as
+    select entryid, author, journal, created, modified, modifiedc, content, significance, tags, edit_time_left
+    from full_entries
+    where not hidden;
+
+create view hidden_entries as
+    select entryid, author, journal, created, modified, modifiedc, content, significance, tags, edit_time_left
+    from full_entries
+    where hidden;
+
+alter table entries
+    drop column publish_at,
+    drop column published;
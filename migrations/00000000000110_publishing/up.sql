@@ -0,0 +1,26 @@
+alter table entries
+    add column published boolean not null default true,
+    add column publish_at timestamp;
+
+create index entries_publish_at on entries (publish_at) where not published;
+
+create or replace view full_entries as
+    select entryid, author, journal, created, modified, modifiedc, content, significance, hidden,
+           (select string_agg(tag,',')
+            from entry_tags
+            where entry=entryid) as tags,
+           edit_time_left(created, journal) as edit_time_left,
+           published, publish_at
+    from entries;
+
+create or replace view visible_entries as
+    select entryid, author, journal, created, modified, modifiedc, content, significance, tags, edit_time_left,
+           published, publish_at
+    from full_entries
+    where not hidden;
+
+create or replace view hidden_entries as
+    select entryid, author, journal, created, modified, modifiedc, content, significance, tags, edit_time_left,
+           published, publish_at
+    from full_entries
+    where hidden;
@@ -0,0 +1,28 @@
+drop view hidden_entries;
+drop view visible_entries;
+drop view full_entries;
+
+create view full_entries as
+    select entryid, author, journal, created, modified, modifiedc, content, significance, hidden,
+           (select string_agg(tag,',')
+            from entry_tags
+            where entry=entryid) as tags,
+           edit_time_left(created, journal) as edit_time_left,
+           published, publish_at
+    from entries;
+
+create view visible_entries as
+    select entryid, author, journal, created, modified, modifiedc, content, significance, tags, edit_time_left,
+           published, publish_at
+    from full_entries
+    where not hidden;
+
+create view hidden_entries as
+    select entryid, author, journal, created, modified, modifiedc, content, significance, tags, edit_time_left,
+           published, publish_at
+    from full_entries
+    where hidden;
+
+alter table entries
+    drop constraint content_length,
+    drop column html;
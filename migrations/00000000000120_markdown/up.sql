@@ -0,0 +1,25 @@
+-- rendered by the server from the markdown in content, entries from before rendering existed are rendered on read
+alter table entries
+    add column html varchar,
+    add constraint content_length check ( char_length(content) <= 50000 ) not valid;
+
+create or replace view full_entries as
+    select entryid, author, journal, created, modified, modifiedc, content, significance, hidden,
+           (select string_agg(tag,',')
+            from entry_tags
+            where entry=entryid) as tags,
+           edit_time_left(created, journal) as edit_time_left,
+           published, publish_at, html
+    from entries;
+
+create or replace view visible_entries as
+    select entryid, author, journal, created, modified, modifiedc, content, significance, tags, edit_time_left,
+           published, publish_at, html
+    from full_entries
+    where not hidden;
+
+create or replace view hidden_entries as
+    select entryid, author, journal, created, modified, modifiedc, content, significance, tags, edit_time_left,
+           published, publish_at, html
+    from full_entries
+    where hidden;
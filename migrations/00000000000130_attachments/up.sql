@@ -0,0 +1,18 @@
+create table attachments
+(
+    attachmentid bigint primary key default id_generator(),
+    entry        bigint       not null references entries on update cascade on delete cascade,
+    filename     varchar(255) not null,
+    mime         varchar(127) not null,
+    size         int          not null,
+    thumbnail    boolean      not null default false,
+    created      timestamp    not null default now()
+);
+
+create index attachments_entry on attachments (entry);
+
+create trigger timestamp_guard
+    before update of created
+    on attachments
+    for each row
+execute procedure timestamp_guard();
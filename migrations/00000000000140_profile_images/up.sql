@@ -0,0 +1,45 @@
+-- the version of the currently uploaded image, which its stored files and urls are keyed by
+alter table profiles
+    add column avatar bigint,
+    add column banner bigint;
+
+create or replace view searchable as
+select u.userid, u.username, u.discriminator, p.summary, p.bio, p.avatar, p.banner
+from profiles p
+         inner join usernames u on p.userid = u.userid
+where p.visibility != 'private';
+
+create or replace view full_profiles as
+select u.userid, u.username, u.discriminator, p.summary, p.bio, p.visibility, a.created, p.modified, u.modified as username_modified,
+       p.avatar, p.banner
+from profiles p
+         inner join usernames u on p.userid = u.userid
+         inner join account_age a on p.userid = a.userid;
+
+create or replace view public_friends as
+select self as userid, friend, p.username, p.discriminator, p.summary, p.bio, since, p.avatar, p.banner
+from (
+         select self, friend, since
+         from (
+                  select user_to as self, user_from as friend, status, since
+                  from relations
+                  union
+                  select user_from as self, user_to as friend, status, since
+                  from relations
+              ) as f
+         where f.status = 'friends'
+     ) as f
+         inner join searchable p on f.friend = p.userid;
+
+create or replace view friend_requests as
+select distinct on (self, friend) self as userid, friend, p.username, p.discriminator, p.summary, p.bio, since, p.avatar, p.banner
+from (
+         select user_to as self, user_from as friend, status, since
+         from relations
+         where status = 'pending_first_second'
+         union
+         select user_from as self, user_to as friend, status, since
+         from relations
+         where status = 'pending_second_first'
+     ) as f
+         inner join searchable p on f.friend = p.userid;
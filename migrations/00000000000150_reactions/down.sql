@@ -0,0 +1,3 @@
+drop table reactions;
+drop function if exists can_react;
+drop type reaction;
@@ -0,0 +1,29 @@
+create type reaction as enum ('heart', 'laugh', 'wow', 'sad', 'hug', 'clap');
+
+create or replace function can_react(me bigint, eid bigint) returns boolean as
+$$
+declare
+    e entries%rowtype;
+begin
+    select * from entries where entryid = eid into e;
+
+    return e.published and not e.hidden and can_see(me, e.author, e.journal);
+end;
+$$ language plpgsql;
+
+create table reactions
+(
+    entry    bigint    not null references entries on update cascade on delete cascade,
+    userid   bigint    not null references profiles on update cascade on delete cascade,
+    reaction reaction  not null,
+    created  timestamp not null default now(),
+
+    constraint can_react check ( can_react(userid, entry) ),
+    primary key (entry, userid, reaction)
+);
+
+create trigger timestamp_guard
+    before update of created
+    on reactions
+    for each row
+execute procedure timestamp_guard();
@@ -0,0 +1,8 @@
+drop table comments;
+
+drop function if exists edit_comment;
+drop function if exists is_top_level;
+drop function if exists can_comment;
+
+alter table journals
+    drop column comments_enabled;
@@ -0,0 +1,62 @@
+alter table journals
+    add column comments_enabled boolean not null default true;
+
+create or replace function can_comment(me bigint, eid bigint) returns boolean as
+$$
+declare
+    enabled boolean;
+begin
+    select j.comments_enabled
+    from entries e
+             inner join journals j on e.journal = j.journalid
+    where e.entryid = eid
+    into enabled;
+
+    return enabled and can_react(me, eid);
+end;
+$$ language plpgsql;
+
+-- replies can only be made to top level comments on the same entry
+create or replace function is_top_level(cid bigint, eid bigint) returns boolean as
+$$
+begin
+    return exists(select 1 from comments c where c.commentid = cid and c.entry = eid and c.parent is null);
+end;
+$$ language plpgsql;
+
+create table comments
+(
+    commentid bigint primary key default id_generator(),
+    entry     bigint    not null references entries on update cascade on delete cascade,
+    author    bigint    not null references profiles on update cascade on delete cascade,
+    parent    bigint references comments on update cascade on delete cascade,
+    created   timestamp not null default now(),
+    modified  timestamp,
+    content   varchar   not null,
+
+    constraint comment_length check ( char_length(content) between 1 and 2000 ),
+    constraint can_comment check ( can_comment(author, entry) ),
+    constraint one_level_replies check ( parent is null or is_top_level(parent, entry) )
+);
+
+create index comments_entry on comments (entry, commentid);
+
+create or replace function edit_comment() returns trigger as
+$$
+begin
+    select now() into new.modified;
+    return new;
+end;
+$$ language plpgsql;
+
+create trigger edit_comment
+    before update
+    on comments
+    for each row
+execute procedure edit_comment();
+
+create trigger timestamp_guard
+    before update of created, modified
+    on comments
+    for each row
+execute procedure timestamp_guard();
@@ -0,0 +1,12 @@
+-- spans are measured in characters of the entry's content
+create table entry_mentions
+(
+    entry  bigint not null references entries on update cascade on delete cascade,
+    userid bigint not null references profiles on update cascade on delete cascade,
+    start  int    not null check ( start >= 0 ),
+    length int    not null check ( length > 0 ),
+
+    primary key (entry, start)
+);
+
+create index entry_mentions_userid on entry_mentions (userid);
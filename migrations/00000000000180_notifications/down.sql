@@ -0,0 +1,8 @@
+drop view inbox;
+
+drop function if exists notify;
+
+drop table notifications;
+drop table notification_preferences;
+
+drop type notification_kind;
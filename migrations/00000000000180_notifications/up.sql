@@ -0,0 +1,56 @@
+create type notification_kind as enum ('friend_request', 'friend_accepted', 'reaction', 'comment', 'reply', 'mention');
+
+-- users receive every kind of notification unless they have turned it off here
+create table notification_preferences
+(
+    userid  bigint            not null references profiles on update cascade on delete cascade,
+    kind    notification_kind not null,
+    enabled boolean           not null,
+
+    primary key (userid, kind)
+);
+
+create table notifications
+(
+    notificationid bigint primary key          default id_generator(),
+    userid         bigint            not null references profiles on update cascade on delete cascade,
+    kind           notification_kind not null,
+    actor          bigint            not null references profiles on update cascade on delete cascade,
+    entry          bigint references entries on update cascade on delete cascade,
+    comment        bigint references comments on update cascade on delete cascade,
+    created        timestamp         not null default now(),
+    read           boolean           not null default false
+);
+
+create index notifications_inbox on notifications (userid, notificationid);
+
+-- returns the new notification's id, or null if the recipient doesn't want it
+create or replace function notify(recipient bigint, what notification_kind, actor_id bigint, entry_id bigint,
+                                  comment_id bigint) returns bigint as
+$$
+declare
+    id bigint;
+begin
+    if recipient = actor_id or exists(select 1
+                                      from notification_preferences p
+                                      where p.userid = recipient
+                                        and p.kind = what
+                                        and not p.enabled) then
+        return null;
+    end if;
+
+    insert into notifications (userid, kind, actor, entry, comment)
+    values (recipient, what, actor_id, entry_id, comment_id)
+    returning notificationid into id;
+
+    return id;
+end;
+$$ language plpgsql;
+
+-- notifications about entries only show up while the recipient can see the entry
+create view inbox as
+select n.notificationid, n.userid, n.kind, n.actor, n.entry, e.journal, n.comment, n.created, n.read
+from notifications n
+         left join entries e on n.entry = e.entryid
+where n.entry isnull
+   or (e.published and not e.hidden and can_see(n.userid, e.author, e.journal));
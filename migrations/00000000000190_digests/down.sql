@@ -0,0 +1,3 @@
+drop table digest_subscriptions;
+
+drop type digest_frequency;
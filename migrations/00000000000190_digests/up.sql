@@ -0,0 +1,10 @@
+create type digest_frequency as enum ('daily', 'weekly');
+
+-- users only receive digests once they have subscribed
+create table digest_subscriptions
+(
+    userid    bigint primary key references accounts on update cascade on delete cascade,
+    frequency digest_frequency not null,
+    created   timestamp        not null default now(),
+    last_sent timestamp
+);
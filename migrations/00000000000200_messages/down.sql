@@ -0,0 +1,7 @@
+drop view conversations;
+
+drop table messages;
+
+drop function if exists send_message;
+drop function if exists are_blocked;
+drop function if exists are_friends;
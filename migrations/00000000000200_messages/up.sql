@@ -0,0 +1,78 @@
+create or replace function are_friends(user1 bigint, user2 bigint) returns boolean as
+$$
+begin
+    return exists(select 1
+                  from relations r
+                  where r.user_from = least(user1, user2)
+                    and r.user_to = greatest(user1, user2)
+                    and r.status = 'friends');
+end;
+$$ language plpgsql;
+
+create or replace function are_blocked(user1 bigint, user2 bigint) returns boolean as
+$$
+begin
+    return exists(select 1
+                  from relations r
+                  where r.user_from = least(user1, user2)
+                    and r.user_to = greatest(user1, user2)
+                    and r.status in ('block_first_second', 'block_second_first', 'block_both'));
+end;
+$$ language plpgsql;
+
+create table messages
+(
+    messageid bigint primary key default id_generator(),
+    sender    bigint    not null references profiles on update cascade on delete cascade,
+    recipient bigint    not null references profiles on update cascade on delete cascade,
+    created   timestamp not null default now(),
+    content   varchar   not null,
+    read      boolean   not null default false,
+
+    constraint message_length check ( char_length(content) between 1 and 4000 )
+);
+
+create index messages_conversation on messages (sender, recipient, messageid);
+
+-- checked on insert only, so conversations stay readable after a friendship ends
+create or replace function send_message() returns trigger as
+$$
+begin
+    if not are_friends(new.sender, new.recipient) then
+        raise check_violation using constraint = 'are_friends';
+    end if;
+
+    return new;
+end;
+$$ language plpgsql;
+
+create trigger send_message
+    before insert
+    on messages
+    for each row
+execute procedure send_message();
+
+create trigger timestamp_guard
+    before update of created
+    on messages
+    for each row
+execute procedure timestamp_guard();
+
+-- one row per user and person they have messaged, hidden once either blocks the other
+create view conversations as
+select c.userid, c.other, u.username, u.discriminator, m.messageid as last_message, m.sender as last_sender,
+       m.created, m.content, c.unread
+from (
+         select userid, other, max(messageid) as last_message, count(*) filter ( where unread ) as unread
+         from (
+                  select sender as userid, recipient as other, messageid, false as unread
+                  from messages
+                  union all
+                  select recipient as userid, sender as other, messageid, not read as unread
+                  from messages
+              ) as sides
+         group by userid, other
+     ) as c
+         inner join messages m on m.messageid = c.last_message
+         inner join usernames u on u.userid = c.other
+where not are_blocked(c.userid, c.other);
@@ -0,0 +1,5 @@
+drop table webhook_deliveries;
+drop table webhook_events;
+drop table webhooks;
+
+drop type webhook_event;
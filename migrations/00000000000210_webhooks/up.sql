@@ -0,0 +1,39 @@
+create type webhook_event as enum ('entry_created', 'friend_request', 'journal_updated', 'ping');
+
+create table webhooks
+(
+    webhookid bigint primary key   default id_generator(),
+    owner     bigint      not null references profiles on update cascade on delete cascade,
+    url       varchar     not null,
+    secret    varchar(64) not null,
+    active    boolean     not null default true,
+    created   timestamp   not null default now(),
+
+    constraint webhook_url check ( url ~ '^https?://[^\s]+$' and char_length(url) <= 2000 )
+);
+
+create table webhook_events
+(
+    webhook bigint        not null references webhooks on update cascade on delete cascade,
+    event   webhook_event not null,
+
+    primary key (webhook, event)
+);
+
+-- next_attempt is cleared once a delivery succeeds or runs out of attempts
+create table webhook_deliveries
+(
+    deliveryid   bigint primary key     default id_generator(),
+    webhook      bigint        not null references webhooks on update cascade on delete cascade,
+    event        webhook_event not null,
+    payload      varchar       not null,
+    created      timestamp     not null default now(),
+    attempts     int           not null default 0,
+    next_attempt timestamp              default now(),
+    delivered    timestamp,
+    status       int,
+    error        varchar
+);
+
+create index webhook_deliveries_pending on webhook_deliveries (next_attempt) where next_attempt is not null;
+create index webhook_deliveries_log on webhook_deliveries (webhook, deliveryid);
@@ -0,0 +1,5 @@
+drop function if exists feed_score;
+drop function if exists closeness;
+
+drop index comments_author;
+drop index reactions_userid;
@@ -0,0 +1,64 @@
+create index reactions_userid on reactions (userid);
+create index comments_author on comments (author);
+
+-- between 0 and 1, how much me has interacted with other's entries and messaged them up to as_of
+create or replace function closeness(me bigint, other bigint, as_of timestamp) returns float as
+$$
+declare
+    reacted   bigint;
+    commented bigint;
+    messaged  bigint;
+begin
+    if me = other then
+        return 1;
+    end if;
+
+    select count(*)
+    from reactions r
+             join entries e on e.entryid = r.entry
+    where r.userid = me
+      and e.author = other
+      and r.created <= as_of
+    into reacted;
+
+    select count(*)
+    from comments c
+             join entries e on e.entryid = c.entry
+    where c.author = me
+      and e.author = other
+      and c.created <= as_of
+    into commented;
+
+    select count(*)
+    from messages
+    where sender = me
+      and recipient = other
+      and created <= as_of
+    into messaged;
+
+    return least(1, (case when are_friends(me, other) then 0.25 else 0 end)
+                        + ln(1 + reacted + 2 * commented + messaged) / 6);
+end;
+$$ language plpgsql stable;
+
+-- ranks an entry for me as it would have been at as_of, so later pages of a feed are scored
+-- the same way as the first. newer, more significant and more reacted to entries from people
+-- me is close to score higher, and every score decays with age
+create or replace function feed_score(me bigint, eid bigint, as_of timestamp) returns float as
+$$
+declare
+    e       entries%rowtype;
+    reacted bigint;
+    age     float;
+begin
+    select * from entries where entryid = eid into e;
+
+    select count(*) from reactions where entry = eid and created <= as_of into reacted;
+
+    age := greatest(extract(epoch from as_of - e.created) / 3600, 0);
+
+    return (1 + ln(1 + greatest(coalesce(e.significance, 0), 0)) + ln(1 + reacted) / 2)
+        * (1 + 2 * closeness(me, e.author, as_of))
+        / power(age + 2, 1.5);
+end;
+$$ language plpgsql stable;
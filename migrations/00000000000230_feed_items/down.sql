@@ -0,0 +1,14 @@
+drop trigger invalidate_visibility on profiles;
+drop trigger invalidate_visibility on journals;
+drop trigger invalidate_relation on relations;
+drop trigger fan_out_entry on entries;
+
+drop function if exists invalidate_visibility;
+drop function if exists invalidate_relation;
+drop function if exists fan_out_entry;
+drop function if exists rebuild_feed;
+drop function if exists invalidate_feed;
+drop function if exists related_users;
+
+drop table feed_invalidations;
+drop table feed_items;
@@ -0,0 +1,140 @@
+-- the entries each user can see, so the feed doesn't have to call can_see for every entry it scans.
+-- can_see is never true without a relation between the users, so only the author and users they
+-- have a relation with are considered
+create table feed_items
+(
+    userid bigint not null references profiles on update cascade on delete cascade,
+    entry  bigint not null references entries on update cascade on delete cascade,
+
+    primary key (userid, entry)
+);
+
+create index feed_items_entry on feed_items (entry);
+
+-- users whose feed_items are out of date, which the feed falls back to can_see for until they are rebuilt
+create table feed_invalidations
+(
+    userid bigint primary key references profiles on update cascade on delete cascade,
+    queued timestamp not null default now()
+);
+
+create or replace function related_users(uid bigint) returns setof bigint as
+$$
+begin
+    return query select user_to from relations where user_from = uid
+                 union
+                 select user_from from relations where user_to = uid;
+end;
+$$ language plpgsql stable;
+
+-- uses the clock rather than the transaction time, so a feed invalidated while the worker is rebuilding it
+-- is always queued after the time the worker started from and stays queued
+create or replace function invalidate_feed(uid bigint) returns void as
+$$
+begin
+    insert into feed_invalidations (userid, queued)
+    values (uid, clock_timestamp())
+    on conflict (userid) do update set queued = clock_timestamp();
+end;
+$$ language plpgsql;
+
+create or replace function rebuild_feed(me bigint) returns bigint as
+$$
+declare
+    added bigint;
+begin
+    delete from feed_items where userid = me;
+
+    insert into feed_items (userid, entry)
+    select me, e.entryid
+    from entries e
+    where e.author = me
+       or (e.author in (select related_users(me)) and can_see(me, e.author, e.journal));
+
+    get diagnostics added = row_count;
+    return added;
+end;
+$$ language plpgsql;
+
+-- fills in the feed of the author and everyone related to them who can see a new entry
+create or replace function fan_out_entry() returns trigger as
+$$
+begin
+    if tg_op = 'UPDATE' then
+        delete from feed_items where entry = new.entryid;
+    end if;
+
+    insert into feed_items (userid, entry)
+    select u, new.entryid
+    from (select new.author as u union select related_users(new.author)) as viewers
+    where can_see(u, new.author, new.journal)
+    on conflict do nothing;
+
+    return new;
+end;
+$$ language plpgsql;
+
+create trigger fan_out_entry
+    after insert or update of journal, author
+    on entries
+    for each row
+execute procedure fan_out_entry();
+
+create or replace function invalidate_relation() returns trigger as
+$$
+begin
+    if tg_op = 'DELETE' then
+        perform invalidate_feed(old.user_from);
+        perform invalidate_feed(old.user_to);
+        return old;
+    end if;
+
+    perform invalidate_feed(new.user_from);
+    perform invalidate_feed(new.user_to);
+    return new;
+end;
+$$ language plpgsql;
+
+create trigger invalidate_relation
+    after insert or update or delete
+    on relations
+    for each row
+execute procedure invalidate_relation();
+
+-- everyone who might see the owner's entries needs their feed rebuilt when the owner's visibility changes
+create or replace function invalidate_visibility() returns trigger as
+$$
+declare
+    uid bigint;
+begin
+    if old.visibility is distinct from new.visibility then
+        if tg_table_name = 'journals' then
+            uid := new.owner;
+        else
+            uid := new.userid;
+        end if;
+
+        perform invalidate_feed(uid);
+        perform invalidate_feed(r) from related_users(uid) as r;
+    end if;
+
+    return new;
+end;
+$$ language plpgsql;
+
+create trigger invalidate_visibility
+    after update of visibility
+    on journals
+    for each row
+execute procedure invalidate_visibility();
+
+create trigger invalidate_visibility
+    after update of visibility
+    on profiles
+    for each row
+execute procedure invalidate_visibility();
+
+-- every feed is built by the worker, and falls back to can_see until then
+insert into feed_invalidations (userid)
+select userid
+from profiles;
@@ -0,0 +1,13 @@
+drop trigger index_entry_tags on entry_tags;
+drop trigger index_entry on entries;
+
+drop function if exists index_entry_tags;
+drop function if exists index_entry;
+drop function if exists refresh_entry_document;
+drop function if exists entry_snippet;
+drop function if exists entry_rank;
+drop function if exists entry_matches;
+drop function if exists entry_query;
+drop function if exists entry_document;
+
+drop table entry_search;
@@ -0,0 +1,95 @@
+-- full text search documents for entries, kept apart from entries so that tagging an entry
+-- doesn't count as editing it. tags are weighted above the content they describe
+create table entry_search
+(
+    entry    bigint primary key references entries on update cascade on delete cascade,
+    document tsvector not null
+);
+
+create index entry_search_document on entry_search using gin (document);
+
+create or replace function entry_document(eid bigint, body text) returns tsvector as
+$$
+begin
+    return setweight(to_tsvector('english', coalesce((select string_agg(tag, ' ') from entry_tags where entry = eid), '')), 'A')
+               || setweight(to_tsvector('english', body), 'B');
+end;
+$$ language plpgsql stable;
+
+-- quoted phrases, or and -negation are understood, and anything else is ignored rather than an error
+create or replace function entry_query(q text) returns tsquery as
+$$
+select websearch_to_tsquery('english', q);
+$$ language sql immutable;
+
+-- simple enough to be inlined, so that searches can use the index
+create or replace function entry_matches(document tsvector, q text) returns boolean as
+$$
+select document @@ entry_query(q);
+$$ language sql immutable;
+
+create or replace function entry_rank(eid bigint, q text) returns real as
+$$
+begin
+    return coalesce((select ts_rank_cd(document, entry_query(q)) from entry_search where entry = eid), 0);
+end;
+$$ language plpgsql stable;
+
+-- the content is escaped first so that the <mark> tags around matches are the only markup in the snippet
+create or replace function entry_snippet(body text, q text) returns text as
+$$
+begin
+    return ts_headline('english',
+                       replace(replace(replace(body, '&', '&amp;'), '<', '&lt;'), '>', '&gt;'),
+                       entry_query(q),
+                       'StartSel=<mark>, StopSel=</mark>, MaxWords=35, MinWords=15, MaxFragments=2, FragmentDelimiter=" … "');
+end;
+$$ language plpgsql immutable;
+
+-- does nothing once the entry is gone, as when its tags are deleted along with it
+create or replace function refresh_entry_document(eid bigint) returns void as
+$$
+begin
+    insert into entry_search (entry, document)
+    select entryid, entry_document(entryid, content)
+    from entries
+    where entryid = eid
+    on conflict (entry) do update set document = excluded.document;
+end;
+$$ language plpgsql;
+
+create or replace function index_entry() returns trigger as
+$$
+begin
+    perform refresh_entry_document(new.entryid);
+    return null;
+end;
+$$ language plpgsql;
+
+create or replace function index_entry_tags() returns trigger as
+$$
+begin
+    if (tg_op = 'DELETE') then
+        perform refresh_entry_document(old.entry);
+    else
+        perform refresh_entry_document(new.entry);
+    end if;
+    return null;
+end;
+$$ language plpgsql;
+
+create trigger index_entry
+    after insert or update of content
+    on entries
+    for each row
+execute procedure index_entry();
+
+create trigger index_entry_tags
+    after insert or delete
+    on entry_tags
+    for each row
+execute procedure index_entry_tags();
+
+insert into entry_search (entry, document)
+select entryid, entry_document(entryid, content)
+from entries;
@@ -0,0 +1,8 @@
+drop function if exists user_score;
+drop function if exists user_matches;
+drop function if exists like_prefix;
+
+drop index profiles_summary_trgm;
+drop index usernames_username_trgm;
+
+drop extension if exists pg_trgm;
@@ -0,0 +1,45 @@
+create extension if not exists pg_trgm;
+
+-- trigram indexes serve both the fuzzy % matches and the prefix like matches
+create index usernames_username_trgm on usernames using gin (lower(username) gin_trgm_ops);
+create index profiles_summary_trgm on profiles using gin (lower(summary) gin_trgm_ops);
+
+create or replace function like_prefix(q text) returns text as
+$$
+select replace(replace(replace(lower(q), '\', '\\'), '%', '\%'), '_', '\_') || '%';
+$$ language sql immutable;
+
+-- simple enough to be inlined, so that searches can use the indexes
+create or replace function user_matches(name text, summary text, q text, summaries boolean) returns boolean as
+$$
+select lower(name) like like_prefix(q)
+           or lower(name) % lower(q)
+           or (summaries and lower(summary) % lower(q));
+$$ language sql immutable;
+
+-- exact names come before prefixes, which come before everything else by similarity,
+-- and friends come before everyone else
+create or replace function user_score(me bigint, uid bigint, name text, summary text, q text, summaries boolean) returns real as
+$$
+declare
+    score real;
+begin
+    score := similarity(lower(name), lower(q));
+
+    if summaries and summary is not null then
+        score := greatest(score, similarity(lower(summary), lower(q)) / 2);
+    end if;
+
+    if lower(name) = lower(q) then
+        score := score + 2;
+    elsif lower(name) like like_prefix(q) then
+        score := score + 1;
+    end if;
+
+    if are_friends(me, uid) then
+        score := score + 4;
+    end if;
+
+    return score;
+end;
+$$ language plpgsql stable;
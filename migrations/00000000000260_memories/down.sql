@@ -0,0 +1,20 @@
+drop function if exists on_this_day;
+drop function if exists local_date;
+
+drop index entries_author;
+
+drop view full_profiles;
+
+create view full_profiles as
+select u.userid, u.username, u.discriminator, p.summary, p.bio, p.visibility, a.created, p.modified, u.modified as username_modified,
+       p.avatar, p.banner
+from profiles p
+         inner join usernames u on p.userid = u.userid
+         inner join account_age a on p.userid = a.userid;
+
+drop trigger check_timezone on profiles;
+
+drop function if exists check_timezone;
+
+alter table profiles
+    drop column timezone;
@@ -0,0 +1,42 @@
+-- the timezone a user's days start and end in. anything postgres accepts as a timezone is allowed
+alter table profiles
+    add column timezone varchar(64) not null default 'UTC';
+
+create or replace function check_timezone() returns trigger as
+$$
+begin
+    perform now() at time zone new.timezone;
+    return new;
+exception
+    when invalid_parameter_value then
+        raise check_violation using constraint = 'timezone';
+end;
+$$ language plpgsql;
+
+create trigger check_timezone
+    before insert or update of timezone
+    on profiles
+    for each row
+execute procedure check_timezone();
+
+create or replace view full_profiles as
+select u.userid, u.username, u.discriminator, p.summary, p.bio, p.visibility, a.created, p.modified, u.modified as username_modified,
+       p.avatar, p.banner, p.timezone
+from profiles p
+         inner join usernames u on p.userid = u.userid
+         inner join account_age a on p.userid = a.userid;
+
+create index entries_author on entries (author, created);
+
+-- timestamps are stored in utc, so they are moved into the timezone from there
+create or replace function local_date(ts timestamp, tz text) returns date as
+$$
+select (ts at time zone 'UTC' at time zone tz)::date;
+$$ language sql stable;
+
+-- whether a timestamp falls on today's month and day in an earlier year, as far as the timezone is concerned
+create or replace function on_this_day(ts timestamp, tz text) returns boolean as
+$$
+select to_char(local_date(ts, tz), 'MM-DD') = to_char(now() at time zone tz, 'MM-DD')
+           and local_date(ts, tz) < date_trunc('year', now() at time zone tz)::date;
+$$ language sql stable;
@@ -0,0 +1,3 @@
+drop function if exists created_between;
+
+drop index entries_created;
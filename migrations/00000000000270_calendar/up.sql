@@ -0,0 +1,10 @@
+create index entries_created on entries (created);
+
+-- whether a timestamp falls between two dates in a timezone. no timezone is more than a day away from utc,
+-- so the timestamp is first narrowed down to the days around them, which can use an index
+create or replace function created_between(ts timestamp, tz text, first date, last date) returns boolean as
+$$
+select ts >= (first - 1)::timestamp
+           and ts < (last + 2)::timestamp
+           and local_date(ts, tz) between first and last;
+$$ language sql stable;
@@ -0,0 +1,11 @@
+drop trigger invalidate_tag_stats on entry_tags;
+drop trigger invalidate_entry_stats on entries;
+
+drop function if exists invalidate_tag_stats;
+drop function if exists invalidate_entry_stats;
+drop function if exists bump_stats;
+
+drop table stats_cache;
+drop table stats_versions;
+
+drop function if exists word_count;
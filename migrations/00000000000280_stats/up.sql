@@ -0,0 +1,82 @@
+create or replace function word_count(body text) returns integer as
+$$
+select count(*)::integer from regexp_matches(body, '\S+', 'g');
+$$ language sql immutable;
+
+-- bumped whenever a user's entries or their tags change. stats are cached along with the version they
+-- were computed from, which is read in the same snapshot as the entries, so a cache can never be newer
+-- than the version it is stored with
+create table stats_versions
+(
+    userid  bigint primary key references profiles on update cascade on delete cascade,
+    version bigint not null default 1
+);
+
+create table stats_cache
+(
+    userid   bigint    not null references profiles on update cascade on delete cascade,
+    scope    varchar   not null,
+    version  bigint    not null,
+    -- streaks depend on the day they were computed on, in the timezone they were computed in
+    day      date      not null,
+    timezone varchar   not null,
+    stats    varchar   not null,
+    computed timestamp not null default now(),
+
+    primary key (userid, scope)
+);
+
+create or replace function bump_stats(uid bigint) returns void as
+$$
+begin
+    insert into stats_versions (userid)
+    values (uid)
+    on conflict (userid) do update set version = stats_versions.version + 1;
+end;
+$$ language plpgsql;
+
+create or replace function invalidate_entry_stats() returns trigger as
+$$
+begin
+    if (tg_op = 'DELETE') then
+        perform bump_stats(old.author);
+    else
+        perform bump_stats(new.author);
+    end if;
+    return null;
+end;
+$$ language plpgsql;
+
+-- does nothing once the entry is gone, which already bumped the version when it was deleted
+create or replace function invalidate_tag_stats() returns trigger as
+$$
+declare
+    eid bigint;
+    uid bigint;
+begin
+    if (tg_op = 'DELETE') then
+        eid := old.entry;
+    else
+        eid := new.entry;
+    end if;
+
+    select author from entries where entryid = eid into uid;
+
+    if uid is not null then
+        perform bump_stats(uid);
+    end if;
+    return null;
+end;
+$$ language plpgsql;
+
+create trigger invalidate_entry_stats
+    after insert or delete or update of content, significance, journal
+    on entries
+    for each row
+execute procedure invalidate_entry_stats();
+
+create trigger invalidate_tag_stats
+    after insert or delete
+    on entry_tags
+    for each row
+execute procedure invalidate_tag_stats();
@@ -0,0 +1,8 @@
+drop trigger queue_profile_images on profiles;
+drop trigger queue_attachment_files on attachments;
+
+drop function if exists queue_profile_images;
+drop function if exists queue_attachment_files;
+
+drop table removed_profile_images;
+drop table removed_attachments;
@@ -0,0 +1,49 @@
+-- stored files whose rows are gone, however they were deleted, for the cleanup job to remove
+create table removed_attachments
+(
+    attachmentid bigint primary key,
+    thumbnail    boolean not null
+);
+
+create table removed_profile_images
+(
+    userid bigint primary key,
+    avatar bigint,
+    banner bigint
+);
+
+create or replace function queue_attachment_files() returns trigger as
+$$
+begin
+    insert into removed_attachments (attachmentid, thumbnail)
+    values (old.attachmentid, old.thumbnail)
+    on conflict do nothing;
+
+    return null;
+end;
+$$ language plpgsql;
+
+create or replace function queue_profile_images() returns trigger as
+$$
+begin
+    if old.avatar is not null or old.banner is not null then
+        insert into removed_profile_images (userid, avatar, banner)
+        values (old.userid, old.avatar, old.banner)
+        on conflict do nothing;
+    end if;
+
+    return null;
+end;
+$$ language plpgsql;
+
+create trigger queue_attachment_files
+    after delete
+    on attachments
+    for each row
+execute procedure queue_attachment_files();
+
+create trigger queue_profile_images
+    after delete
+    on profiles
+    for each row
+execute procedure queue_profile_images();
@@ -0,0 +1,24 @@
+create or replace view full_entries as
+    select entryid, author, journal, created, modified, modifiedc, content, significance, hidden,
+           (select string_agg(tag,',')
+            from entry_tags
+            where entry=entryid) as tags,
+           edit_time_left(created, journal) as edit_time_left,
+           published, publish_at, html
+    from entries;
+
+create or replace function edit_entry() returns trigger as
+$$
+begin
+    select now() into new.modified;
+    if (new.content != old.content) then
+        if (edit_time_left(old.created, old.journal) <= 0) then
+            raise check_violation using constraint = 'edit_window';
+        end if;
+
+        select now() into new.modifiedc;
+    end if;
+
+    return new;
+end;
+$$ language plpgsql;
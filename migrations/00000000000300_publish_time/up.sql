@@ -0,0 +1,32 @@
+-- entries count as written when they are published, so the edit window starts then and drafts and
+-- scheduled entries can be edited until they go out. created is moved here rather than in the update
+-- itself, which timestamp_guard would reject
+create or replace function edit_entry() returns trigger as
+$$
+begin
+    select now() into new.modified;
+
+    if (new.published and not old.published) then
+        select now() into new.created;
+    end if;
+
+    if (new.content != old.content) then
+        if (old.published and edit_time_left(old.created, old.journal) <= 0) then
+            raise check_violation using constraint = 'edit_window';
+        end if;
+
+        select now() into new.modifiedc;
+    end if;
+
+    return new;
+end;
+$$ language plpgsql;
+
+create or replace view full_entries as
+    select entryid, author, journal, created, modified, modifiedc, content, significance, hidden,
+           (select string_agg(tag,',')
+            from entry_tags
+            where entry=entryid) as tags,
+           case when published then edit_time_left(created, journal) end as edit_time_left,
+           published, publish_at, html
+    from entries;
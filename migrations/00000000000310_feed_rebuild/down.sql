@@ -0,0 +1,17 @@
+create or replace function rebuild_feed(me bigint) returns bigint as
+$$
+declare
+    added bigint;
+begin
+    delete from feed_items where userid = me;
+
+    insert into feed_items (userid, entry)
+    select me, e.entryid
+    from entries e
+    where e.author = me
+       or (e.author in (select related_users(me)) and can_see(me, e.author, e.journal));
+
+    get diagnostics added = row_count;
+    return added;
+end;
+$$ language plpgsql;
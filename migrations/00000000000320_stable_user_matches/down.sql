@@ -0,0 +1,6 @@
+create or replace function user_matches(name text, summary text, q text, summaries boolean) returns boolean as
+$$
+select lower(name) like like_prefix(q)
+           or lower(name) % lower(q)
+           or (summaries and lower(summary) % lower(q));
+$$ language sql immutable;
@@ -0,0 +1,9 @@
+-- % is only stable, since it depends on pg_trgm.similarity_threshold, and an immutable sql function that
+-- calls something less than immutable is never inlined. Declared stable it is inlined again, so searches
+-- can use the trigram indexes
+create or replace function user_matches(name text, summary text, q text, summaries boolean) returns boolean as
+$$
+select lower(name) like like_prefix(q)
+           or lower(name) % lower(q)
+           or (summaries and lower(summary) % lower(q));
+$$ language sql stable;
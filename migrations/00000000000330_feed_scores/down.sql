@@ -0,0 +1,23 @@
+drop function if exists feed_scores;
+
+-- ranks an entry for me as it would have been at as_of, so later pages of a feed are scored
+-- the same way as the first. newer, more significant and more reacted to entries from people
+-- me is close to score higher, and every score decays with age
+create or replace function feed_score(me bigint, eid bigint, as_of timestamp) returns float as
+$$
+declare
+    e       entries%rowtype;
+    reacted bigint;
+    age     float;
+begin
+    select * from entries where entryid = eid into e;
+
+    select count(*) from reactions where entry = eid and created <= as_of into reacted;
+
+    age := greatest(extract(epoch from as_of - e.created) / 3600, 0);
+
+    return (1 + ln(1 + greatest(coalesce(e.significance, 0), 0)) + ln(1 + reacted) / 2)
+        * (1 + 2 * closeness(me, e.author, as_of))
+        / power(age + 2, 1.5);
+end;
+$$ language plpgsql stable;
@@ -0,0 +1,37 @@
+-- scores a set of entries for me as they would have been at as_of, working out closeness once for each
+-- author and counting reactions in one pass rather than for every entry. newer, more significant and
+-- more reacted to entries from people me is close to score higher, and every score decays with age
+create or replace function feed_scores(me bigint, eids bigint[], as_of timestamp)
+    returns table
+            (
+                entry bigint,
+                score float
+            )
+as
+$$
+with candidates as (
+    select entryid, author, created, significance
+    from entries
+    where entryid = any (eids)
+),
+     authors as (
+         select a.author, closeness(me, a.author, as_of) as closeness
+         from (select distinct author from candidates) a
+     ),
+     reacted as (
+         select r.entry, count(*) as reacted
+         from reactions r
+         where r.entry = any (eids)
+           and r.created <= as_of
+         group by r.entry
+     )
+select c.entryid,
+       ((1 + ln(1 + greatest(coalesce(c.significance, 0), 0)) + ln(1 + coalesce(r.reacted, 0)) / 2)
+           * (1 + 2 * a.closeness)
+           / power(greatest(extract(epoch from as_of - c.created)::float / 3600, 0) + 2, 1.5))::float
+from candidates c
+         join authors a on a.author = c.author
+         left join reacted r on r.entry = c.entryid;
+$$ language sql stable;
+
+drop function if exists feed_score;
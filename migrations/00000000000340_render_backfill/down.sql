@@ -0,0 +1,22 @@
+create or replace function edit_entry() returns trigger as
+$$
+begin
+    select now() into new.modified;
+
+    if (new.published and not old.published) then
+        select now() into new.created;
+    end if;
+
+    if (new.content != old.content) then
+        if (old.published and edit_time_left(old.created, old.journal) <= 0) then
+            raise check_violation using constraint = 'edit_window';
+        end if;
+
+        select now() into new.modifiedc;
+    end if;
+
+    return new;
+end;
+$$ language plpgsql;
+
+drop index entries_unrendered;
@@ -0,0 +1 @@
+drop index messages_received;
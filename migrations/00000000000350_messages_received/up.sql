@@ -0,0 +1,2 @@
+-- conversations are read from both sides, and messages_conversation only serves the sender's
+create index messages_received on messages (recipient, sender, messageid);
@@ -0,0 +1,2 @@
+alter table stats_cache
+    alter column stats type varchar using stats::text;
@@ -0,0 +1,28 @@
+-- lists are paged by id, so a published entry also gets a new one. otherwise an entry drafted long ago
+-- lands deep in older history, where clients polling for newer entries never see it. everything that
+-- references the entry follows it through on update cascade
+create or replace function edit_entry() returns trigger as
+$$
+begin
+    if (old.html is null and new.html is not null and new.content = old.content) then
+        return new;
+    end if;
+
+    select now() into new.modified;
+
+    if (new.published and not old.published) then
+        select now() into new.created;
+        select id_generator() into new.entryid;
+    end if;
+
+    if (new.content != old.content) then
+        if (old.published and edit_time_left(old.created, old.journal) <= 0) then
+            raise check_violation using constraint = 'edit_window';
+        end if;
+
+        select now() into new.modifiedc;
+    end if;
+
+    return new;
+end;
+$$ language plpgsql;
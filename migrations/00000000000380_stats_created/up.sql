@@ -0,0 +1,7 @@
+drop trigger invalidate_entry_stats on entries;
+
+create trigger invalidate_entry_stats
+    after insert or delete or update of content, significance, journal, created
+    on entries
+    for each row
+execute procedure invalidate_entry_stats();
@@ -1,6 +1,7 @@
 use std::error::Error as STDError;
 
 use actix_web::{HttpResponse, ResponseError};
+use actix_web::error::BlockingError;
 use diesel::result::{DatabaseErrorKind, Error as DBError};
 use serde::export::TryFrom;
 
@@ -30,10 +31,20 @@ pub enum Error {
 pub enum ConstraintViolation {
     AuthorOwnsJournal,
     ProperEmail,
-    EditAfterDay,
+    EditWindow,
     EditTimestamp,
     ArePublic,
     HandleNotAvailable,
+    ImmutableRevision,
+    ContentLength,
+    CanReact,
+    CommentLength,
+    CanComment,
+    OneLevelReplies,
+    AreFriends,
+    MessageLength,
+    WebhookUrl,
+    Timezone,
 }
 
 impl STDError for Error {}
@@ -79,10 +90,23 @@ impl<'a> From<DBError> for Error {
     }
 }
 
+impl From<BlockingError<Error>> for Error {
+    fn from(error: BlockingError<Error>) -> Self {
+        match error {
+            BlockingError::Error(e) => e,
+            BlockingError::Canceled => Error::InternalServerError
+        }
+    }
+}
+
 impl From<r2d2::Error> for Error {
     fn from(_: r2d2::Error) -> Self { Error::InternalServerError }
 }
 
+impl From<std::io::Error> for Error {
+    fn from(_: std::io::Error) -> Self { Error::InternalServerError }
+}
+
 impl std::convert::TryFrom<&str> for ConstraintViolation {
     type Error = ();
 
@@ -90,10 +114,20 @@ impl std::convert::TryFrom<&str> for ConstraintViolation {
         match value {
             "author_owns_journal" => Ok(ConstraintViolation::AuthorOwnsJournal),
             "proper_email" => Ok(ConstraintViolation::ProperEmail),
-            "edit_after_day" => Ok(ConstraintViolation::EditAfterDay),
+            "edit_window" => Ok(ConstraintViolation::EditWindow),
             "edit_timestamp" => Ok(ConstraintViolation::EditTimestamp),
             "are_public" => Ok(ConstraintViolation::ArePublic),
             "handle_not_available" => Ok(ConstraintViolation::HandleNotAvailable),
+            "immutable_revision" => Ok(ConstraintViolation::ImmutableRevision),
+            "content_length" => Ok(ConstraintViolation::ContentLength),
+            "can_react" => Ok(ConstraintViolation::CanReact),
+            "comment_length" => Ok(ConstraintViolation::CommentLength),
+            "can_comment" => Ok(ConstraintViolation::CanComment),
+            "one_level_replies" => Ok(ConstraintViolation::OneLevelReplies),
+            "are_friends" => Ok(ConstraintViolation::AreFriends),
+            "message_length" => Ok(ConstraintViolation::MessageLength),
+            "webhook_url" => Ok(ConstraintViolation::WebhookUrl),
+            "timezone" => Ok(ConstraintViolation::Timezone),
             _ => Err(())
         }
     }
@@ -104,10 +138,20 @@ impl From<ConstraintViolation> for Error {
         match cv {
             ConstraintViolation::AuthorOwnsJournal => Error::BadRequest("user does not own journal".into()),
             ConstraintViolation::ProperEmail => Error::BadRequest("please provide a valid email address".into()),
-            ConstraintViolation::EditAfterDay => Error::BadRequest("cannot edit the content of an entry 24 hours after it's creation".into()),
+            ConstraintViolation::EditWindow => Error::BadRequest("the edit window for this entry has closed".into()),
             ConstraintViolation::EditTimestamp => Error::BadRequest("cannot edit a timestamp".into()),
             ConstraintViolation::ArePublic => Error::BadRequest("both users must have non-private profiles".into()),
             ConstraintViolation::HandleNotAvailable => Error::BadRequest("there are too many users with that name already".into()),
+            ConstraintViolation::ImmutableRevision => Error::BadRequest("revisions cannot be changed".into()),
+            ConstraintViolation::ContentLength => Error::BadRequest("entries are limited to 50000 characters".into()),
+            ConstraintViolation::CanReact => Error::BadRequest("cannot react to that entry".into()),
+            ConstraintViolation::CommentLength => Error::BadRequest("comments must be between 1 and 2000 characters".into()),
+            ConstraintViolation::CanComment => Error::BadRequest("cannot comment on that entry".into()),
+            ConstraintViolation::OneLevelReplies => Error::BadRequest("can only reply to top level comments on the same entry".into()),
+            ConstraintViolation::AreFriends => Error::BadRequest("can only message friends".into()),
+            ConstraintViolation::MessageLength => Error::BadRequest("messages must be between 1 and 4000 characters".into()),
+            ConstraintViolation::WebhookUrl => Error::BadRequest("webhooks need an http or https url of at most 2000 characters".into()),
+            ConstraintViolation::Timezone => Error::BadRequest("unknown timezone".into()),
         }
     }
 }
\ No newline at end of file
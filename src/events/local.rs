@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use diesel::PgConnection;
+use futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use crate::errors::ValyouResult;
+use crate::events::{Event, PubSub};
+
+/// Hands events straight to the clients connected to this instance.
+pub struct LocalHub {
+    subscribers: Mutex<HashMap<i64, Vec<UnboundedSender<Event>>>>
+}
+
+impl LocalHub {
+    pub fn new() -> Self {
+        LocalHub { subscribers: Mutex::new(HashMap::new()) }
+    }
+
+    /// Sends an event to every connection the user has open, forgetting the ones that have closed.
+    pub fn deliver(&self, user: i64, event: &Event) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+
+        if let Some(senders) = subscribers.get_mut(&user) {
+            senders.retain(|tx| tx.unbounded_send(event.clone()).is_ok());
+
+            if senders.is_empty() {
+                subscribers.remove(&user);
+            }
+        }
+    }
+}
+
+impl PubSub for LocalHub {
+    fn publish(&self, user: i64, event: Event, _db: &PgConnection) -> ValyouResult<()> {
+        self.deliver(user, &event);
+
+        Ok(())
+    }
+
+    /// Also forgets the user's connections that have closed since, so users who reconnect without
+    /// ever receiving an event don't pile up senders.
+    fn subscribe(&self, user: i64) -> UnboundedReceiver<Event> {
+        let (tx, rx) = mpsc::unbounded();
+
+        let mut subscribers = self.subscribers.lock().unwrap();
+        let senders = subscribers.entry(user).or_default();
+
+        senders.retain(|tx| !tx.is_closed());
+        senders.push(tx);
+
+        rx
+    }
+}
@@ -0,0 +1,67 @@
+use std::sync::Arc;
+
+use diesel::PgConnection;
+use futures::channel::mpsc::UnboundedReceiver;
+
+use crate::errors::ValyouResult;
+use crate::models;
+use crate::models::notifications::NotificationKind;
+
+pub use self::local::LocalHub;
+pub use self::pg::PgPubSub;
+
+mod local;
+mod pg;
+
+/// The pub/sub backend the server was configured with, shared between workers.
+pub type Events = Arc<dyn PubSub>;
+
+/// Something a connected client should know about. Events only carry ids, so clients fetch
+/// whatever changed through the usual endpoints and visibility is checked there.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// A new entry for the user's feed.
+    Entry {
+        #[serde(with = "models::id_serde")]
+        id: i64,
+        #[serde(with = "models::id_serde")]
+        journal: i64,
+        #[serde(with = "models::id_serde")]
+        author: i64
+    },
+    FriendRequest {
+        #[serde(with = "models::id_serde")]
+        from: i64
+    },
+    Message {
+        #[serde(with = "models::id_serde")]
+        id: i64,
+        #[serde(with = "models::id_serde")]
+        from: i64
+    },
+    Notification {
+        #[serde(with = "models::id_serde")]
+        id: i64,
+        kind: NotificationKind
+    }
+}
+
+/// Delivers events to the users they are addressed to, wherever those users are connected.
+pub trait PubSub: Send + Sync {
+    /// Publishes on the caller's connection, so an event sent inside a transaction goes out
+    /// only once it commits and publishing never waits on a second connection from the pool.
+    fn publish(&self, user: i64, event: Event, db: &PgConnection) -> ValyouResult<()>;
+
+    /// Receives the user's events until the receiver is dropped.
+    fn subscribe(&self, user: i64) -> UnboundedReceiver<Event>;
+}
+
+/// Picks the pub/sub backend from `EVENTS_BACKEND`. The default only reaches clients connected
+/// to this instance, while `postgres` fans out to every instance through LISTEN/NOTIFY.
+pub fn from_env(database_url: &str) -> Events {
+    match dotenv::var("EVENTS_BACKEND").as_ref().map(String::as_str) {
+        Ok("postgres") => Arc::new(PgPubSub::new(database_url.into())),
+        _ => Arc::new(LocalHub::new())
+    }
+}
@@ -0,0 +1,80 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use diesel::prelude::*;
+use diesel::sql_types::Text;
+use fallible_iterator::FallibleIterator;
+use futures::channel::mpsc::UnboundedReceiver;
+use postgres::{Client, NoTls};
+
+use crate::errors::{Error, ValyouResult};
+use crate::events::{Event, LocalHub, PubSub};
+
+const CHANNEL: &str = "valyou_events";
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    user: i64,
+    event: Event
+}
+
+/// Publishes events with NOTIFY and hears them back on a dedicated LISTEN connection,
+/// so clients receive events published by any instance.
+pub struct PgPubSub {
+    hub: Arc<LocalHub>
+}
+
+impl PgPubSub {
+    pub fn new(database_url: String) -> Self {
+        let hub = Arc::new(LocalHub::new());
+        let listener = hub.clone();
+
+        thread::spawn(move || loop {
+            if let Err(e) = listen(&database_url, &listener) {
+                log::error!("event listener failed: {}", e);
+            }
+
+            thread::sleep(RECONNECT_DELAY);
+        });
+
+        PgPubSub { hub }
+    }
+}
+
+/// Delivers every event heard on the channel to this instance's clients until the connection drops.
+fn listen(database_url: &str, hub: &LocalHub) -> Result<(), postgres::Error> {
+    let mut client = Client::connect(database_url, NoTls)?;
+    client.batch_execute(&format!("listen {}", CHANNEL))?;
+
+    let mut notifications = client.notifications();
+    let mut heard = notifications.blocking_iter();
+
+    while let Some(notification) = heard.next()? {
+        match serde_json::from_str::<Envelope>(notification.payload()) {
+            Ok(Envelope { user, event }) => hub.deliver(user, &event),
+            Err(e) => log::warn!("ignoring malformed event: {}", e)
+        }
+    }
+
+    Ok(())
+}
+
+impl PubSub for PgPubSub {
+    fn publish(&self, user: i64, event: Event, db: &PgConnection) -> ValyouResult<()> {
+        let payload = serde_json::to_string(&Envelope { user, event })
+            .map_err(|_| Error::InternalServerError)?;
+
+        diesel::sql_query("select pg_notify($1, $2)")
+            .bind::<Text, _>(CHANNEL)
+            .bind::<Text, _>(payload)
+            .execute(db)?;
+
+        Ok(())
+    }
+
+    fn subscribe(&self, user: i64) -> UnboundedReceiver<Event> {
+        self.hub.subscribe(user)
+    }
+}
@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use diesel::prelude::*;
+
+use crate::errors::{Error, ValyouResult};
+use crate::models::attachments::{key, thumbnail_key};
+use crate::models::profiles::{AVATAR_SIZES, avatar_key, banner_key};
+use crate::Pool;
+use crate::storage::Storage;
+
+pub const INTERVAL: Duration = Duration::from_secs(60);
+
+/// The most rows of each kind cleaned up in one run.
+const BATCH_SIZE: i64 = 200;
+
+/// Removes the stored files of attachments and profiles that have been deleted, including by cascade
+/// from their entries, journals or accounts. Rows are only dropped once their files are gone, so a
+/// failed removal is retried on the next run.
+pub fn run(pool: &Pool, storage: &Storage) -> ValyouResult<()> {
+    let db = pool.get()?;
+
+    let attachments = db.transaction::<_, Error, _>(|| {
+        use crate::schema::removed_attachments::dsl::*;
+
+        let removed: Vec<(i64, bool)> = removed_attachments
+            .order(attachmentid.asc())
+            .limit(BATCH_SIZE)
+            .for_update()
+            .skip_locked()
+            .get_results(&db)?;
+
+        for &(id, thumb) in &removed {
+            storage.delete(&key(id))?;
+
+            if thumb {
+                storage.delete(&thumbnail_key(id))?;
+            }
+        }
+
+        let ids: Vec<i64> = removed.iter().map(|&(id, _)| id).collect();
+
+        Ok(diesel::delete(removed_attachments)
+            .filter(attachmentid.eq_any(ids))
+            .execute(&db)?)
+    })?;
+
+    let profiles = db.transaction::<_, Error, _>(|| {
+        use crate::schema::removed_profile_images::dsl::*;
+
+        let removed: Vec<(i64, Option<i64>, Option<i64>)> = removed_profile_images
+            .order(userid.asc())
+            .limit(BATCH_SIZE)
+            .for_update()
+            .skip_locked()
+            .get_results(&db)?;
+
+        for &(user, old_avatar, old_banner) in &removed {
+            if let Some(version) = old_avatar {
+                for &size in AVATAR_SIZES {
+                    storage.delete(&avatar_key(user, version, size))?;
+                }
+            }
+
+            if let Some(version) = old_banner {
+                storage.delete(&banner_key(user, version))?;
+            }
+        }
+
+        let users: Vec<i64> = removed.iter().map(|&(user, _, _)| user).collect();
+
+        Ok(diesel::delete(removed_profile_images)
+            .filter(userid.eq_any(users))
+            .execute(&db)?)
+    })?;
+
+    if attachments + profiles > 0 {
+        log::info!("removed the files of {} attachments and {} profiles", attachments, profiles);
+    }
+
+    Ok(())
+}
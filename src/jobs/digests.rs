@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use diesel::dsl::now;
+use diesel::prelude::*;
+
+use crate::errors::ValyouResult;
+use crate::mail::{digest, Mail};
+use crate::models::can_see;
+use crate::models::digests::{Digest, DigestEntry, excerpt, MAX_DIGEST_ENTRIES, Subscription};
+use crate::models::entries::Entry;
+use crate::Pool;
+use crate::routes::relationships::friend_ids;
+
+pub const INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Sends a digest to everyone whose daily or weekly period has passed since their last one.
+pub fn run(pool: &Pool, mail: &Mail) -> ValyouResult<()> {
+    let db = pool.get()?;
+
+    let current: chrono::NaiveDateTime = diesel::select(now).get_result(&db)?;
+
+    let subscriptions: Vec<Subscription> = {
+        use crate::schema::digest_subscriptions::dsl::*;
+
+        digest_subscriptions.get_results(&db)?
+    };
+
+    for subscription in subscriptions.iter().filter(|s| s.is_due(current)) {
+        if let Err(e) = send(subscription, current, mail, &db) {
+            log::error!("failed to send digest to {}: {}", subscription.userid, e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Sends one digest, skipping the email if nothing happened but still starting the next period.
+fn send(subscription: &Subscription, current: chrono::NaiveDateTime, mail: &Mail, db: &PgConnection) -> ValyouResult<()> {
+    let me = subscription.userid;
+    let digest = collect(subscription, db)?;
+
+    if !digest.is_empty() {
+        let address: String = {
+            use crate::schema::accounts::dsl::*;
+
+            accounts
+                .select(email)
+                .filter(userid.eq(me))
+                .get_result(db)?
+        };
+
+        mail.send(digest::render(&digest, address)?)?;
+    }
+
+    use crate::schema::digest_subscriptions::dsl::*;
+    diesel::update(digest_subscriptions)
+        .filter(userid.eq(me))
+        .set(last_sent.eq(current))
+        .execute(db)?;
+
+    Ok(())
+}
+
+fn collect(subscription: &Subscription, db: &PgConnection) -> ValyouResult<Digest> {
+    let me = subscription.userid;
+    let period_start = subscription.since();
+    let friends = friend_ids(me, db)?;
+
+    let (found, total): (Vec<Entry>, i64) = {
+        use crate::views::visible_entries::dsl::*;
+
+        let new_entries = || visible_entries
+            .filter(author.eq_any(friends.clone()).and(created.gt(period_start)).and(published))
+            .filter(can_see(me, author, journal));
+
+        (
+            new_entries()
+                .order(entryid.desc())
+                .limit(MAX_DIGEST_ENTRIES as i64)
+                .get_results(db)?,
+            new_entries()
+                .count()
+                .get_result(db)?
+        )
+    };
+
+    let handles: HashMap<i64, String> = {
+        use crate::schema::usernames::dsl::*;
+
+        usernames
+            .filter(userid.eq_any(found.iter().map(|e| e.author).collect::<Vec<_>>()))
+            .select((userid, username, discriminator))
+            .get_results::<(i64, String, i16)>(db)?
+            .into_iter()
+            .map(|(id, name, tag)| (id, format!("{}#{:04}", name, tag)))
+            .collect()
+    };
+
+    let titles: HashMap<i64, String> = {
+        use crate::schema::journals::dsl::*;
+
+        journals
+            .filter(journalid.eq_any(found.iter().map(|e| e.journal).collect::<Vec<_>>()))
+            .select((journalid, title))
+            .get_results::<(i64, String)>(db)?
+            .into_iter()
+            .collect()
+    };
+
+    let requests: Vec<String> = {
+        use crate::views::friend_requests::dsl::*;
+
+        friend_requests
+            .filter(userid.eq(me))
+            .order(since.asc())
+            .select((username, discriminator))
+            .get_results::<(String, i16)>(db)?
+            .into_iter()
+            .map(|(name, tag)| format!("{}#{:04}", name, tag))
+            .collect()
+    };
+
+    let unread: i64 = {
+        use crate::views::inbox::dsl::*;
+
+        inbox
+            .filter(userid.eq(me).and(read.eq(false)))
+            .count()
+            .get_result(db)?
+    };
+
+    let username: String = {
+        use crate::schema::usernames::dsl::*;
+
+        usernames
+            .select(username)
+            .filter(userid.eq(me))
+            .get_result(db)?
+    };
+
+    let entries = found.into_iter()
+        .map(|e| DigestEntry {
+            author: handles.get(&e.author).cloned().unwrap_or_default(),
+            journal: titles.get(&e.journal).cloned().unwrap_or_default(),
+            created: e.created,
+            excerpt: excerpt(&e.content)
+        })
+        .collect::<Vec<_>>();
+
+    Ok(Digest {
+        username,
+        frequency: subscription.frequency,
+        more_entries: total as usize - entries.len(),
+        entries,
+        requests,
+        unread
+    })
+}
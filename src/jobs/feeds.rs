@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use diesel::prelude::*;
+
+use crate::errors::ValyouResult;
+use crate::models::rebuild_feed;
+use crate::Pool;
+
+pub const INTERVAL: Duration = Duration::from_secs(5);
+
+/// The most feeds rebuilt in one run, so a burst of invalidations is worked through over several.
+const BATCH_SIZE: i64 = 100;
+
+/// Rebuilds the feeds that have been invalidated, oldest first. Feeds invalidated again
+/// while being rebuilt stay queued for the next run, and feeds another instance is already
+/// rebuilding are skipped.
+pub fn run(pool: &Pool) -> ValyouResult<()> {
+    use crate::schema::feed_invalidations::dsl::*;
+
+    let db = pool.get()?;
+
+    let stale: Vec<i64> = feed_invalidations
+        .select(userid)
+        .order(queued.asc())
+        .limit(BATCH_SIZE)
+        .get_results(&db)?;
+
+    let mut rebuilt = 0;
+
+    for &user in &stale {
+        rebuilt += db.transaction::<_, diesel::result::Error, _>(|| {
+            let claimed: Option<chrono::NaiveDateTime> = feed_invalidations
+                .find(user)
+                .select(queued)
+                .for_update()
+                .skip_locked()
+                .get_result(&db)
+                .optional()?;
+
+            match claimed {
+                Some(when) => {
+                    diesel::select(rebuild_feed(user)).execute(&db)?;
+
+                    diesel::delete(feed_invalidations)
+                        .filter(userid.eq(user).and(queued.le(when)))
+                        .execute(&db)
+                },
+                None => Ok(0)
+            }
+        })?;
+    }
+
+    if rebuilt > 0 {
+        log::info!("rebuilt {} feeds", rebuilt);
+    }
+
+    Ok(())
+}
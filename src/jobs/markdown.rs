@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use diesel::prelude::*;
+
+use crate::errors::ValyouResult;
+use crate::markdown;
+use crate::Pool;
+
+pub const INTERVAL: Duration = Duration::from_secs(60);
+
+/// The most entries rendered in one run.
+const BATCH_SIZE: i64 = 200;
+
+/// Renders and stores the html of entries from before it was stored, so that reading them doesn't
+/// render them every time. Once every entry has been rendered a run is a single indexed query.
+pub fn run(pool: &Pool) -> ValyouResult<()> {
+    use crate::schema::entries::dsl::*;
+
+    let db = pool.get()?;
+
+    let unrendered: Vec<(i64, String)> = entries
+        .select((entryid, content))
+        .filter(html.is_null())
+        .order(entryid.asc())
+        .limit(BATCH_SIZE)
+        .get_results(&db)?;
+
+    // an entry edited in the meantime has been rendered already, and is left alone
+    for (id, source) in &unrendered {
+        diesel::update(entries.find(id))
+            .filter(html.is_null())
+            .set(html.eq(markdown::render(source)))
+            .execute(&db)?;
+    }
+
+    if !unrendered.is_empty() {
+        log::info!("rendered {} entries", unrendered.len());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+use actix_web::web;
+
+use crate::errors::ValyouResult;
+use crate::events::Events;
+use crate::mail::Mail;
+use crate::Pool;
+use crate::storage::Storage;
+
+mod publish;
+mod digests;
+mod webhooks;
+mod feeds;
+mod blobs;
+mod markdown;
+
+/// Spawns every background job onto the current actix system.
+pub fn start(pool: Pool, events: Events, mail: Mail, storage: Storage) {
+    every(publish::INTERVAL, pool.clone(), move |pool| publish::run(pool, &events));
+    every(digests::INTERVAL, pool.clone(), move |pool| digests::run(pool, &mail));
+    every(webhooks::INTERVAL, pool.clone(), webhooks::run);
+    every(feeds::INTERVAL, pool.clone(), feeds::run);
+    every(markdown::INTERVAL, pool.clone(), markdown::run);
+    every(blobs::INTERVAL, pool, move |pool| blobs::run(pool, &storage));
+}
+
+/// Runs a blocking job on the thread pool at a fixed interval, logging any failures.
+fn every<F>(period: Duration, pool: Pool, job: F)
+    where F: Fn(&Pool) -> ValyouResult<()> + Send + Clone + 'static {
+    actix_rt::spawn(async move {
+        let mut interval = actix_rt::time::interval(period);
+
+        loop {
+            interval.tick().await;
+
+            let pool = pool.clone();
+            let job = job.clone();
+            if let Err(e) = web::block(move || job(&pool)).await {
+                log::error!("background job failed: {}", e);
+            }
+        }
+    });
+}
@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+use diesel::dsl::now;
+use diesel::prelude::*;
+
+use crate::errors::ValyouResult;
+use crate::events::Events;
+use crate::Pool;
+use crate::routes::events::entry_published;
+use crate::routes::mentions;
+
+pub const INTERVAL: Duration = Duration::from_secs(30);
+
+/// Publishes every scheduled entry whose `publish_at` has passed. Publishing gives them a new id and moves
+/// `created` up to now, so they show up in feeds and digests as new, and only then are mentions resolved.
+pub fn run(pool: &Pool, events: &Events) -> ValyouResult<()> {
+    let db = pool.get()?;
+
+    let released: Vec<(i64, i64, i64, String)> = {
+        use crate::schema::entries::dsl::*;
+
+        diesel::update(entries)
+            .filter(published.eq(false).and(publish_at.le(now.nullable())))
+            .set(published.eq(true))
+            .returning((entryid, author, journal, content))
+            .get_results(&db)?
+    };
+
+    if !released.is_empty() {
+        log::info!("published {} scheduled entries", released.len());
+    }
+
+    // the entries are published already, so one failing to be announced doesn't hold up the rest
+    for (eid, author, journal, content) in released {
+        let announced = mentions::save(eid, &content, events, &db)
+            .and_then(|_| entry_published(eid, author, journal, events, &db));
+
+        if let Err(e) = announced {
+            log::error!("could not announce published entry {:019}: {}", eid, e);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use std::time::Duration;
+
+use diesel::dsl::{IntervalDsl, now};
+use diesel::prelude::*;
+
+use crate::errors::ValyouResult;
+use crate::models::webhooks::{backoff, check_url, Delivery, MAX_ATTEMPTS, sign};
+use crate::Pool;
+use crate::schema::{webhook_deliveries, webhooks};
+
+pub const INTERVAL: Duration = Duration::from_secs(10);
+
+/// The most deliveries attempted in one run, so a slow receiver can't hold up the job for long.
+const BATCH_SIZE: i64 = 50;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How many seconds claimed deliveries are left alone by other instances, which is longer than a run can take.
+const LEASE: i32 = 15 * 60;
+
+/// Attempts every delivery that is due, rescheduling failures with exponential backoff.
+pub fn run(pool: &Pool) -> ValyouResult<()> {
+    let db = pool.get()?;
+
+    // deliveries are claimed by pushing their next attempt past the lease, so that other instances
+    // skip them while they are being attempted and pick them up again if this one goes away
+    let claimed: Vec<i64> = db.transaction::<_, diesel::result::Error, _>(|| {
+        use crate::schema::webhook_deliveries::dsl::*;
+
+        let active = webhooks::table
+            .filter(webhooks::active)
+            .select(webhooks::webhookid);
+
+        let due: Vec<i64> = webhook_deliveries
+            .select(deliveryid)
+            .filter(next_attempt.le(now.nullable()).and(webhook.eq_any(active)))
+            .order(next_attempt.asc())
+            .limit(BATCH_SIZE)
+            .for_update()
+            .skip_locked()
+            .get_results(&db)?;
+
+        diesel::update(webhook_deliveries)
+            .filter(deliveryid.eq_any(&due))
+            .set(next_attempt.eq((now + LEASE.seconds()).nullable()))
+            .execute(&db)?;
+
+        Ok(due)
+    })?;
+
+    let due: Vec<(Delivery, String, String)> = webhook_deliveries::table
+        .inner_join(webhooks::table)
+        .filter(webhook_deliveries::deliveryid.eq_any(&claimed).and(webhooks::active))
+        .order(webhook_deliveries::deliveryid.asc())
+        .select((webhook_deliveries::all_columns, webhooks::url, webhooks::secret))
+        .get_results(&db)?;
+
+    for (delivery, url, secret) in due {
+        attempt(delivery, &url, &secret, &db)?;
+    }
+
+    Ok(())
+}
+
+fn attempt(delivery: Delivery, url: &str, secret: &str, db: &PgConnection) -> ValyouResult<()> {
+    use crate::schema::webhook_deliveries::dsl::*;
+
+    // checked again right before sending, in case the host has been pointed somewhere private since
+    let outcome = match check_url(url) {
+        Err(reason) => Err((None, reason)),
+        Ok(checked) => {
+            let mut request = ureq::post(checked.url.as_str());
+
+            // the url now points at the checked address, so the receiver is told which host was meant
+            if let Some(host) = &checked.host {
+                request.set("Host", host);
+            }
+
+            let response = request
+                .set("Content-Type", "application/json")
+                .set("User-Agent", "Valyou-Webhooks")
+                .set("X-Valyou-Event", delivery.event.name())
+                .set("X-Valyou-Delivery", &format!("{:019}", delivery.id))
+                .set("X-Valyou-Signature", &format!("sha256={}", sign(secret, &delivery.payload)))
+                .timeout(TIMEOUT)
+                .redirects(0)
+                .send_string(&delivery.payload);
+
+            let code = if response.synthetic() { None } else { Some(response.status() as i32) };
+
+            if response.ok() {
+                Ok(code)
+            } else {
+                let reason = match response.synthetic_error() {
+                    Some(e) => e.to_string(),
+                    None => format!("receiver responded with {}", response.status())
+                };
+
+                Err((code, reason))
+            }
+        }
+    };
+
+    let tries = delivery.attempts + 1;
+    let target = webhook_deliveries.filter(deliveryid.eq(delivery.id));
+
+    match outcome {
+        Ok(code) => {
+            diesel::update(target)
+                .set((
+                    attempts.eq(tries),
+                    status.eq(code),
+                    error.eq(None::<String>),
+                    delivered.eq(now.nullable()),
+                    next_attempt.eq(None::<chrono::NaiveDateTime>)
+                ))
+                .execute(db)?;
+        },
+        Err((code, reason)) => {
+            let failed = (attempts.eq(tries), status.eq(code), error.eq(Some(reason)));
+
+            if tries < MAX_ATTEMPTS {
+                diesel::update(target)
+                    .set((failed, next_attempt.eq((now + backoff(tries).seconds()).nullable())))
+                    .execute(db)?;
+            } else {
+                diesel::update(target)
+                    .set((failed, next_attempt.eq(None::<chrono::NaiveDateTime>)))
+                    .execute(db)?;
+            }
+        }
+    }
+
+    Ok(())
+}
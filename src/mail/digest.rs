@@ -0,0 +1,34 @@
+use tera::{Context, Tera};
+
+use crate::errors::{Error, ValyouResult};
+use crate::mail::Message;
+use crate::models::digests::{Digest, DigestFrequency};
+
+const HTML: &str = include_str!("templates/digest.html");
+const TEXT: &str = include_str!("templates/digest.txt");
+
+/// Renders a digest into an email for the given address.
+pub fn render(digest: &Digest, to: String) -> ValyouResult<Message> {
+    let mut tera = Tera::default();
+    tera.add_raw_templates(vec![("digest.html", HTML), ("digest.txt", TEXT)])
+        .map_err(template_error)?;
+
+    let context = Context::from_serialize(digest).map_err(template_error)?;
+
+    let subject = match digest.frequency {
+        DigestFrequency::Daily => "Your daily Valyou digest",
+        DigestFrequency::Weekly => "Your weekly Valyou digest"
+    };
+
+    Ok(Message {
+        to,
+        subject: subject.into(),
+        text: tera.render("digest.txt", &context).map_err(template_error)?,
+        html: tera.render("digest.html", &context).map_err(template_error)?
+    })
+}
+
+fn template_error(e: tera::Error) -> Error {
+    log::error!("failed to render digest: {}", e);
+    Error::InternalServerError
+}
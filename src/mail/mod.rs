@@ -0,0 +1,105 @@
+use std::fs;
+use std::sync::{Arc, Mutex};
+
+use lettre::{FileTransport, SendableEmail, SmtpClient, SmtpTransport, Transport};
+use lettre::smtp::authentication::Credentials;
+use lettre_email::EmailBuilder;
+
+use crate::errors::{Error, ValyouResult};
+
+pub mod digest;
+
+/// The mail transport the server was configured with, shared between workers and jobs.
+pub type Mail = Arc<dyn Mailer>;
+
+/// An email with both a plain text and an html body.
+#[derive(Debug)]
+pub struct Message {
+    pub to: String,
+    pub subject: String,
+    pub text: String,
+    pub html: String
+}
+
+pub trait Mailer: Send + Sync {
+    fn send(&self, message: Message) -> ValyouResult<()>;
+}
+
+/// Picks the mail transport from `MAIL_TRANSPORT`. The default writes every message to the
+/// `MAIL_SPOOL` directory instead of sending it, while `smtp` relays through `SMTP_HOST`.
+pub fn from_env() -> ValyouResult<Mail> {
+    let from = dotenv::var("MAIL_FROM").unwrap_or_else(|_| "noreply@localhost".into());
+
+    match dotenv::var("MAIL_TRANSPORT").as_ref().map(String::as_str) {
+        Ok("smtp") => {
+            let host = dotenv::var("SMTP_HOST").map_err(|_| Error::InternalServerError)?;
+            let mut client = SmtpClient::new_simple(&host).map_err(|_| Error::InternalServerError)?;
+
+            if let (Ok(username), Ok(password)) = (dotenv::var("SMTP_USERNAME"), dotenv::var("SMTP_PASSWORD")) {
+                client = client.credentials(Credentials::new(username, password));
+            }
+
+            Ok(Arc::new(SmtpMailer { from, transport: Mutex::new(client.transport()) }))
+        },
+        _ => {
+            let spool = dotenv::var("MAIL_SPOOL").unwrap_or_else(|_| "mail".into());
+            fs::create_dir_all(&spool)?;
+
+            Ok(Arc::new(SpoolMailer { from, transport: Mutex::new(FileTransport::new(spool)) }))
+        }
+    }
+}
+
+fn build(from: &str, message: Message) -> ValyouResult<SendableEmail> {
+    let email = EmailBuilder::new()
+        .to(message.to)
+        .from((from, "Valyou"))
+        .subject(message.subject)
+        .alternative(message.html, message.text)
+        .build()
+        .map_err(|_| Error::InternalServerError)?;
+
+    Ok(email.into())
+}
+
+/// Relays messages through an SMTP server.
+pub struct SmtpMailer {
+    from: String,
+    transport: Mutex<SmtpTransport>
+}
+
+impl Mailer for SmtpMailer {
+    fn send(&self, message: Message) -> ValyouResult<()> {
+        let email = build(&self.from, message)?;
+
+        self.transport.lock().unwrap()
+            .send(email)
+            .map_err(|e| {
+                log::error!("failed to send mail: {}", e);
+                Error::InternalServerError
+            })?;
+
+        Ok(())
+    }
+}
+
+/// Writes each message to a file in a directory, for development and testing.
+pub struct SpoolMailer {
+    from: String,
+    transport: Mutex<FileTransport>
+}
+
+impl Mailer for SpoolMailer {
+    fn send(&self, message: Message) -> ValyouResult<()> {
+        let email = build(&self.from, message)?;
+
+        self.transport.lock().unwrap()
+            .send(email)
+            .map_err(|e| {
+                log::error!("failed to spool mail: {}", e);
+                Error::InternalServerError
+            })?;
+
+        Ok(())
+    }
+}
@@ -15,11 +15,19 @@ use diesel::r2d2::{self, ConnectionManager};
 use dotenv;
 use env_logger;
 
+use models::attachments::MAX_ATTACHMENT_SIZE;
+use models::profiles::MAX_IMAGE_SIZE;
+
 mod models;
 mod schema;
 mod views;
 mod errors;
 mod routes;
+mod jobs;
+mod markdown;
+mod storage;
+mod events;
+mod mail;
 
 type Pool = r2d2::Pool<ConnectionManager<PgConnection>>;
 
@@ -30,7 +38,9 @@ async fn missing() -> impl Responder {
 #[actix_rt::main]
 async fn main() -> io::Result<()> {
     dotenv::dotenv().ok();
-    std::env::set_var("RUST_LOG", "actix_web=info");
+    if std::env::var_os("RUST_LOG").is_none() {
+        std::env::set_var("RUST_LOG", "actix_web=info,valyou=info");
+    }
     env_logger::init();
 
     let manager = ConnectionManager::<PgConnection>::new(dotenv!("DATABASE_URL"));
@@ -38,10 +48,20 @@ async fn main() -> io::Result<()> {
         .build(manager)
         .expect("Failed to create pool.");
 
+    let events = events::from_env(dotenv!("DATABASE_URL"));
+
+    let mail = mail::from_env().expect("Failed to configure mail.");
+
+    let storage = storage::from_env();
+
+    jobs::start(pool.clone(), events.clone(), mail, storage.clone());
+
     HttpServer::new(move || {
         use routes::*;
         App::new()
             .data(pool.clone())
+            .data(storage.clone())
+            .data(events.clone())
             .wrap(IdentityService::new(
                 CookieIdentityPolicy::new(dotenv::var("COOKIE_SECRET").unwrap().as_bytes())
                     .name("valauth")
@@ -60,22 +80,66 @@ async fn main() -> io::Result<()> {
                     )
             )
             .service(web::scope("/user")
-                .route("", web::get().to(profiles::search))
+                .route("", web::get().to(profiles::search_first))
+                .route("/search/{method}", web::get().to(profiles::search))
                 .service(web::scope("/self")
                     .route("/timeline/{method}", web::get().to(feed::timeline))
                     .route("/feed/{method}", web::get().to(feed::feed))
+                    .route("/hidden/{method}", web::get().to(feed::hidden))
+                    .route("/search/{method}", web::get().to(feed::search))
+                    .route("/memories", web::get().to(feed::memories))
+                    .route("/calendar/{year}/{month}", web::get().to(calendar::own_month))
+                    .route("/days/{method}", web::get().to(calendar::days))
+                    .route("/stats", web::get().to(stats::own))
+                    .route("/events", web::get().to(routes::events::stream))
+                    .route("/digest", web::get().to(digests::view))
+                    .route("/digest", web::put().to(digests::subscribe))
+                    .route("/digest", web::delete().to(digests::unsubscribe))
+                    .route("/conversations/{method}", web::get().to(messages::conversations))
                     .route("/journals/{method}", web::get().to(journals::get_own_journals))
-                    .route("/friends", web::get().to(relationships::view_own_friends))
-                    .route("/friends/request", web::get().to(relationships::show_requests))
+                    .route("/friends", web::get().to(relationships::first_friends))
+                    .route("/friends/request", web::get().to(relationships::first_requests))
+                    .route("/friends/request/{method}", web::get().to(relationships::show_requests))
+                    .route("/friends/{method}", web::get().to(relationships::view_own_friends))
+                    .service(web::scope("/notifications")
+                        .route("/preferences", web::get().to(notifications::preferences))
+                        .route("/preferences", web::patch().to(notifications::edit_preferences))
+                        .route("/read", web::put().to(notifications::mark_all_read))
+                        .route("/{method}", web::get().to(notifications::inbox))
+                        .route("/{notificationid}/read", web::put().to(notifications::mark_read))
+                    )
+                    .service(web::scope("/webhooks")
+                        .route("", web::get().to(webhooks::list))
+                        .route("", web::post().to(webhooks::create))
+                        .route("/{webhookid}", web::patch().to(webhooks::edit))
+                        .route("/{webhookid}", web::delete().to(webhooks::delete))
+                        .route("/{webhookid}/ping", web::post().to(webhooks::ping))
+                        .route("/{webhookid}/deliveries/{method}", web::get().to(webhooks::deliveries))
+                    )
                     .service(web::scope("/profile")
                         .route("", web::get().to(profiles::view_self))
                         .route("", web::patch().to(profiles::edit))
                         .route("/username", web::patch().to(profiles::change_username))
+                        .route("/avatar", web::put()
+                            .data(web::PayloadConfig::new(MAX_IMAGE_SIZE))
+                            .to(profiles::set_avatar))
+                        .route("/avatar", web::delete().to(profiles::delete_avatar))
+                        .route("/banner", web::put()
+                            .data(web::PayloadConfig::new(MAX_IMAGE_SIZE))
+                            .to(profiles::set_banner))
+                        .route("/banner", web::delete().to(profiles::delete_banner))
                     )
                 )
                 .service(web::scope("/{userid}")
                     .route("/journals/{method}", web::get().to(journals::get_user_journals))
                     .route("/profile", web::get().to(profiles::view))
+                    .route("/avatar", web::get().to(profiles::avatar))
+                    .route("/banner", web::get().to(profiles::banner))
+                    .service(web::scope("/messages")
+                        .route("", web::post().to(messages::send))
+                        .route("/read", web::put().to(messages::mark_read))
+                        .route("/{method}", web::get().to(messages::list))
+                    )
                     .service(web::scope("/friends")
                         .route("", web::delete().to(relationships::remove_friend))
                         .service(web::scope("/request")
@@ -92,13 +156,40 @@ async fn main() -> io::Result<()> {
                 .service(web::scope("/{journalid}")
                     .route("", web::get().to(journals::find))
                     .route("", web::patch().to(journals::edit))
+                    .route("/stats", web::get().to(stats::journal))
                     .route("/{method}", web::get().to(entries::in_journal))
+                    .route("/calendar/{year}/{month}", web::get().to(calendar::journal_month))
+                    .route("/days/{method}", web::get().to(calendar::journal_days))
                     .service(web::scope("/entries")
                         .route("", web::post().to(entries::create))
                         .service(web::scope("/{entryid}")
                             .route("", web::get().to(entries::find))
                             .route("", web::patch().to(entries::edit))
+                            .route("", web::delete().to(entries::delete))
                             .route("/tags", web::patch().to(entries::edit))
+                            .route("/hidden", web::put().to(entries::hide))
+                            .route("/hidden", web::delete().to(entries::unhide))
+                            .route("/published", web::put().to(entries::publish))
+                            .route("/published", web::delete().to(entries::unpublish))
+                            .route("/revisions/{method}", web::get().to(revisions::list))
+                            .route("/diff", web::get().to(revisions::diff))
+                            .route("/reactions/{reaction}", web::put().to(reactions::react))
+                            .route("/reactions/{reaction}", web::delete().to(reactions::unreact))
+                            .route("/comments", web::post().to(comments::create))
+                            .route("/comments/{method}", web::get().to(comments::list))
+                            .route("/comments/{commentid}", web::patch().to(comments::edit))
+                            .route("/comments/{commentid}", web::delete().to(comments::delete))
+                            .service(web::scope("/attachments")
+                                .route("", web::get().to(attachments::list))
+                                .route("", web::post()
+                                    .data(web::PayloadConfig::new(MAX_ATTACHMENT_SIZE))
+                                    .to(attachments::upload))
+                                .service(web::scope("/{attachmentid}")
+                                    .route("", web::get().to(attachments::download))
+                                    .route("", web::delete().to(attachments::delete))
+                                    .route("/thumbnail", web::get().to(attachments::thumbnail))
+                                )
+                            )
                         )
                     )
                 )
@@ -0,0 +1,26 @@
+use std::collections::HashSet;
+
+use ammonia::Builder;
+use pulldown_cmark::{html, Options, Parser};
+
+/// The tags that survive sanitization, everything else is stripped but keeps its text.
+const ALLOWED_TAGS: &[&str] = &[
+    "p", "br", "hr", "em", "strong", "del", "a", "code", "pre", "blockquote",
+    "ul", "ol", "li", "h1", "h2", "h3", "h4", "h5", "h6",
+];
+
+/// Renders the CommonMark subset supported in entries to HTML that is safe to display as is.
+pub fn render(source: &str) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+
+    let mut rendered = String::with_capacity(source.len() + source.len() / 2);
+    html::push_html(&mut rendered, Parser::new_ext(source, options));
+
+    Builder::default()
+        .tags(ALLOWED_TAGS.iter().cloned().collect::<HashSet<_>>())
+        .url_schemes(["http", "https", "mailto"].iter().cloned().collect::<HashSet<_>>())
+        .link_rel(Some("noopener noreferrer nofollow"))
+        .clean(&rendered)
+        .to_string()
+}
@@ -0,0 +1,60 @@
+use crate::models;
+
+/// The largest file that can be attached to an entry, in bytes.
+pub const MAX_ATTACHMENT_SIZE: usize = 10 * 1024 * 1024;
+
+/// The side length of the square that image thumbnails are scaled to fit in.
+pub const THUMBNAIL_SIZE: u32 = 320;
+
+/// Everything that isn't an image and can still be attached.
+pub const FILE_TYPES: &[&str] = &["application/pdf", "text/plain"];
+
+#[derive(Debug, Serialize, Queryable)]
+pub struct Attachment {
+    #[serde(with = "models::id_serde")]
+    pub id: i64,
+    #[serde(with = "models::id_serde")]
+    pub entry: i64,
+    pub filename: String,
+    pub mime: String,
+    pub size: i32,
+    pub thumbnail: bool,
+    pub created: chrono::NaiveDateTime,
+}
+
+impl Attachment {
+    pub fn key(&self) -> String {
+        key(self.id)
+    }
+
+    pub fn thumbnail_key(&self) -> String {
+        thumbnail_key(self.id)
+    }
+}
+
+#[inline(always)]
+pub fn key(id: i64) -> String {
+    format!("attachments/{:019}", id)
+}
+
+#[inline(always)]
+pub fn thumbnail_key(id: i64) -> String {
+    format!("attachments/{:019}.thumb.png", id)
+}
+
+/// Keeps only the final path component of an uploaded file's name, without anything that could break out of a header.
+pub fn clean_filename(name: &str) -> String {
+    let name: String = name.rsplit(|c| c == '/' || c == '\\')
+        .next()
+        .unwrap_or("")
+        .chars()
+        .filter(|c| !c.is_control() && *c != '"')
+        .take(255)
+        .collect();
+
+    if name.trim().is_empty() {
+        "attachment".into()
+    } else {
+        name
+    }
+}
@@ -0,0 +1,27 @@
+use crate::models;
+use crate::models::search::Keyed;
+
+#[derive(Debug, Serialize, Queryable)]
+pub struct Comment {
+    #[serde(with = "models::id_serde")]
+    pub id: i64,
+    #[serde(with = "models::id_serde")]
+    pub entry: i64,
+    #[serde(with = "models::id_serde")]
+    pub author: i64,
+    /// The top level comment this is a reply to.
+    #[serde(skip_serializing_if = "Option::is_none", with = "models::option_id_serde")]
+    pub parent: Option<i64>,
+    pub created: chrono::NaiveDateTime,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified: Option<chrono::NaiveDateTime>,
+    pub content: String
+}
+
+impl Keyed for Comment {
+    type Key = i64;
+
+    fn key(&self) -> i64 {
+        self.id
+    }
+}
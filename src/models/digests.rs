@@ -0,0 +1,113 @@
+use std::io::Write;
+
+use diesel::{deserialize, serialize};
+use diesel::deserialize::FromSql;
+use diesel::pg::Pg;
+use diesel::serialize::{IsNull, Output, ToSql};
+
+/// The most entries listed in one digest, newest first.
+pub const MAX_DIGEST_ENTRIES: usize = 20;
+
+/// How much of each entry's content is quoted in a digest, in characters.
+pub const EXCERPT_LENGTH: usize = 200;
+
+pub mod db {
+    #[derive(SqlType, QueryId)]
+    #[postgres(type_name = "digest_frequency")]
+    pub struct DigestFrequency;
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, FromSqlRow, AsExpression)]
+#[sql_type = "db::DigestFrequency"]
+#[serde(rename_all = "lowercase")]
+pub enum DigestFrequency {
+    Daily,
+    Weekly
+}
+
+impl DigestFrequency {
+    pub fn period(self) -> chrono::Duration {
+        match self {
+            DigestFrequency::Daily => chrono::Duration::days(1),
+            DigestFrequency::Weekly => chrono::Duration::weeks(1)
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Queryable)]
+pub struct Subscription {
+    #[serde(skip)]
+    pub userid: i64,
+    pub frequency: DigestFrequency,
+    pub created: chrono::NaiveDateTime,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_sent: Option<chrono::NaiveDateTime>
+}
+
+impl Subscription {
+    /// The start of the period the next digest covers.
+    pub fn since(&self) -> chrono::NaiveDateTime {
+        self.last_sent.unwrap_or(self.created)
+    }
+
+    pub fn is_due(&self, now: chrono::NaiveDateTime) -> bool {
+        self.since() + self.frequency.period() <= now
+    }
+}
+
+/// Everything a digest email is rendered from.
+#[derive(Debug, Serialize)]
+pub struct Digest {
+    pub username: String,
+    pub frequency: DigestFrequency,
+    pub entries: Vec<DigestEntry>,
+    /// How many new entries there were beyond the ones listed.
+    pub more_entries: usize,
+    /// The `name#1234` handles of everyone waiting on a friend request.
+    pub requests: Vec<String>,
+    pub unread: i64
+}
+
+impl Digest {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty() && self.requests.is_empty() && self.unread == 0
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DigestEntry {
+    pub author: String,
+    pub journal: String,
+    pub created: chrono::NaiveDateTime,
+    pub excerpt: String
+}
+
+pub fn excerpt(content: &str) -> String {
+    let mut excerpt: String = content.chars().take(EXCERPT_LENGTH).collect();
+
+    if excerpt.len() < content.len() {
+        excerpt.push('…');
+    }
+
+    excerpt
+}
+
+impl ToSql<db::DigestFrequency, Pg> for DigestFrequency {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, Pg>) -> serialize::Result {
+        match *self {
+            DigestFrequency::Daily => out.write_all(b"daily")?,
+            DigestFrequency::Weekly => out.write_all(b"weekly")?,
+        }
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<db::DigestFrequency, Pg> for DigestFrequency {
+    fn from_sql(bytes: Option<&[u8]>) -> deserialize::Result<Self> {
+        match not_none!(bytes) {
+            b"daily" => Ok(DigestFrequency::Daily),
+            b"weekly" => Ok(DigestFrequency::Weekly),
+            _ => Err("Unrecognized enum variant".into()),
+        }
+    }
+}
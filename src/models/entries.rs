@@ -1,6 +1,30 @@
 use diesel::{Queryable, sql_types::*};
 
+use crate::errors::ConstraintViolation;
+use crate::markdown;
 use crate::models;
+use crate::models::mentions::Mention;
+use crate::models::reactions::ReactionCount;
+use crate::models::search::Keyed;
+
+/// The longest an entry's markdown source can be, in characters.
+pub const MAX_CONTENT_LENGTH: usize = 50000;
+
+/// How far back the ranked feed looks for entries, in days.
+pub const RANKED_WINDOW: i64 = 14;
+
+/// The most entries shown as memories on any one day.
+pub const MAX_MEMORIES: i64 = 100;
+
+/// The longest a search can be, in characters.
+pub const MAX_QUERY_LENGTH: usize = 256;
+
+pub mod db {
+    /// Full text search documents are only ever matched against in queries and never loaded.
+    #[derive(SqlType, QueryId)]
+    #[postgres(type_name = "tsvector")]
+    pub struct TsVector;
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Entry {
@@ -16,33 +40,45 @@ pub struct Entry {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub modifiedc: Option<chrono::NaiveDateTime>,
     pub content: String,
+    /// The sanitized rendering of `content`, which clients should display instead of rendering it themselves.
+    pub html: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub significance: Option<f64>,
-    pub tags: Vec<String>
+    pub tags: Vec<String>,
+    /// Seconds until the content can no longer be edited, or `None` if the journal has no limit or the
+    /// entry hasn't been published yet.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edit_time_left: Option<i64>,
+    pub published: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub publish_at: Option<chrono::NaiveDateTime>,
+    #[serde(default)]
+    pub reactions: Vec<ReactionCount>,
+    #[serde(default)]
+    pub mentions: Vec<Mention>
 }
 
-impl Queryable<(BigInt, BigInt, BigInt, Timestamp, Nullable<Timestamp>, Nullable<Timestamp>, Text, Nullable<Double>, Text), diesel::pg::Pg> for Entry {
-    type Row = (i64, i64, i64, chrono::NaiveDateTime, Option<chrono::NaiveDateTime>, Option<chrono::NaiveDateTime>, String, Option<f64>, String);
+impl Entry {
+    #[inline(always)]
+    pub fn can_edit_content(&self) -> bool {
+        self.edit_time_left.map_or(true, |left| left > 0)
+    }
+}
 
-    fn build(row: Self::Row) -> Self {
-        Entry {
-            id: row.0,
-            author: row.1,
-            journal: row.2,
-            created: row.3,
-            modified: row.4,
-            modifiedc: row.5,
-            content: row.6,
-            significance: row.7,
-            tags: row.8.split(',').map(|s| s.into()).collect()
-        }
+pub fn check_content(content: &str) -> Result<(), ConstraintViolation> {
+    if content.chars().count() > MAX_CONTENT_LENGTH {
+        Err(ConstraintViolation::ContentLength)
+    } else {
+        Ok(())
     }
 }
 
-impl Queryable<(BigInt, BigInt, BigInt, Timestamp, Nullable<Timestamp>, Nullable<Timestamp>, Text, Nullable<Double>), diesel::pg::Pg> for Entry {
-    type Row = (i64, i64, i64, chrono::NaiveDateTime, Option<chrono::NaiveDateTime>, Option<chrono::NaiveDateTime>, String, Option<f64>);
+impl Queryable<(BigInt, BigInt, BigInt, Timestamp, Nullable<Timestamp>, Nullable<Timestamp>, Text, Nullable<Double>, Text, Nullable<BigInt>, Bool, Nullable<Timestamp>, Nullable<Text>), diesel::pg::Pg> for Entry {
+    type Row = (i64, i64, i64, chrono::NaiveDateTime, Option<chrono::NaiveDateTime>, Option<chrono::NaiveDateTime>, String, Option<f64>, String, Option<i64>, bool, Option<chrono::NaiveDateTime>, Option<String>);
 
     fn build(row: Self::Row) -> Self {
+        let html = row.12.unwrap_or_else(|| markdown::render(&row.6));
+
         Entry {
             id: row.0,
             author: row.1,
@@ -51,8 +87,138 @@ impl Queryable<(BigInt, BigInt, BigInt, Timestamp, Nullable<Timestamp>, Nullable
             modified: row.4,
             modifiedc: row.5,
             content: row.6,
+            html,
             significance: row.7,
-            tags: Vec::with_capacity(0)
+            tags: row.8.split(',').map(|s| s.into()).collect(),
+            edit_time_left: row.9,
+            published: row.10,
+            publish_at: row.11,
+            reactions: Vec::new(),
+            mentions: Vec::new()
         }
     }
-}
\ No newline at end of file
+}
+
+impl Keyed for Entry {
+    type Key = i64;
+
+    fn key(&self) -> i64 {
+        self.id
+    }
+}
+
+/// An entry in the ranked feed, along with the time it was ranked at.
+#[derive(Debug, Serialize)]
+pub struct Ranked {
+    #[serde(flatten)]
+    pub entry: Entry,
+    pub score: f64,
+    #[serde(skip)]
+    pub as_of: chrono::NaiveDateTime
+}
+
+/// How an entry scored in the ranked feed, as returned by `feed_scores`.
+#[derive(Debug, QueryableByName)]
+pub struct Score {
+    #[sql_type = "BigInt"]
+    pub entry: i64,
+    #[sql_type = "Double"]
+    pub score: f64
+}
+
+impl Keyed for Ranked {
+    type Key = (Option<chrono::NaiveDateTime>, u64, i64);
+
+    fn key(&self) -> Self::Key {
+        (Some(self.as_of), self.score.to_bits(), self.entry.id)
+    }
+}
+
+/// An entry found by a search, along with how well it matched.
+#[derive(Debug, Serialize)]
+pub struct Match {
+    #[serde(flatten)]
+    pub entry: Entry,
+    pub rank: f32,
+    /// The parts of the content that matched, escaped and with each match wrapped in `<mark>`.
+    pub snippet: String
+}
+
+impl Keyed for Match {
+    type Key = (u32, i64);
+
+    fn key(&self) -> Self::Key {
+        (self.rank.to_bits(), self.entry.id)
+    }
+}
+
+/// The entries written on today's date in one earlier year.
+#[derive(Debug, Serialize)]
+pub struct Memories {
+    pub year: i32,
+    pub entries: Vec<Entry>
+}
+
+/// How many entries were written on each day of a month that has any.
+#[derive(Debug, Serialize)]
+pub struct Calendar {
+    pub year: i32,
+    pub month: u32,
+    pub days: Vec<Day>
+}
+
+#[derive(Debug, Serialize)]
+pub struct Day {
+    pub date: chrono::NaiveDate,
+    pub entries: i64
+}
+
+#[derive(Debug, Clone, Serialize, Queryable)]
+pub struct Revision {
+    #[serde(with = "models::id_serde")]
+    pub id: i64,
+    #[serde(with = "models::id_serde")]
+    pub entry: i64,
+    pub created: chrono::NaiveDateTime,
+    pub content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub significance: Option<f64>
+}
+
+impl Keyed for Revision {
+    type Key = i64;
+
+    fn key(&self) -> i64 {
+        self.id
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "op", content = "line", rename_all = "lowercase")]
+pub enum DiffLine {
+    Equal(String),
+    Insert(String),
+    Delete(String)
+}
+
+#[derive(Debug, Serialize)]
+pub struct RevisionDiff {
+    pub from: Revision,
+    pub to: Revision,
+    pub lines: Vec<DiffLine>
+}
+
+impl RevisionDiff {
+    pub fn new(from: Revision, to: Revision) -> Self {
+        let lines = diff::lines(&from.content, &to.content)
+            .into_iter()
+            .map(|line| match line {
+                diff::Result::Left(l) => DiffLine::Delete(l.into()),
+                diff::Result::Both(l, _) => DiffLine::Equal(l.into()),
+                diff::Result::Right(r) => DiffLine::Insert(r.into()),
+            })
+            .collect();
+
+        RevisionDiff { from, to, lines }
+    }
+}
@@ -0,0 +1,142 @@
+use crate::models;
+
+/// Only this many mentions are resolved in any one entry.
+pub const MAX_MENTIONS: usize = 50;
+
+const MIN_USERNAME: usize = 2;
+const MAX_USERNAME: usize = 32;
+const DISCRIMINATOR_DIGITS: usize = 4;
+
+/// A resolved `name#1234` handle in an entry's content, measured in characters.
+#[derive(Debug, Serialize, Deserialize, Queryable)]
+pub struct Mention {
+    #[serde(with = "models::id_serde")]
+    pub user: i64,
+    pub start: i32,
+    pub length: i32
+}
+
+/// Something in an entry's content that looks like a handle.
+///
+/// Usernames can contain most punctuation, so the text before the `#` could be surrounded by
+/// markdown or joined to the previous word. Every suffix of it long enough to be a username is kept,
+/// and resolving prefers the longest one that exists.
+#[derive(Debug)]
+pub struct Candidate {
+    /// Where each possible username starts, longest first.
+    pub names: Vec<(usize, String)>,
+    pub discriminator: i16,
+    /// The character just past the discriminator.
+    pub end: usize
+}
+
+fn is_username_char(c: char) -> bool {
+    !c.is_whitespace() && c != '#' && c != '@' && c != ':'
+}
+
+pub fn parse(content: &str) -> Vec<Candidate> {
+    let chars: Vec<char> = content.chars().collect();
+    let mut found = Vec::new();
+    let mut last_end = 0;
+
+    for (i, &c) in chars.iter().enumerate() {
+        if c != '#' {
+            continue;
+        }
+
+        let digits = &chars[i + 1..];
+        let len = digits.iter().take_while(|d| d.is_ascii_digit()).count();
+
+        if len != DISCRIMINATOR_DIGITS {
+            continue;
+        }
+
+        let discriminator: i16 = match digits[..len].iter().collect::<String>().parse() {
+            Ok(0) | Err(_) => continue,
+            Ok(d) => d
+        };
+
+        let start = chars[..i].iter().rev().take_while(|&&c| is_username_char(c)).count();
+        let start = (i - start.min(MAX_USERNAME)).max(last_end);
+
+        let names: Vec<(usize, String)> = (start..i)
+            .filter(|s| i - s >= MIN_USERNAME)
+            .map(|s| (s, chars[s..i].iter().collect()))
+            .collect();
+
+        if !names.is_empty() {
+            last_end = i + 1 + len;
+            found.push(Candidate { names, discriminator, end: last_end });
+        }
+
+        if found.len() == MAX_MENTIONS {
+            break;
+        }
+    }
+
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(candidate: &Candidate) -> Vec<&str> {
+        candidate.names.iter().map(|(_, name)| name.as_str()).collect()
+    }
+
+    #[test]
+    fn finds_handles() {
+        let found = parse("hi alice#1234!");
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(names(&found[0]), vec!["alice", "lice", "ice", "ce"]);
+        assert_eq!(found[0].names[0].0, 3);
+        assert_eq!(found[0].discriminator, 1234);
+        assert_eq!(found[0].end, 13);
+    }
+
+    #[test]
+    fn keeps_names_joined_to_markdown() {
+        let found = parse("**bob#0001**");
+
+        assert_eq!(names(&found[0]), vec!["**bob", "*bob", "bob", "ob"]);
+        assert_eq!(found[0].discriminator, 1);
+    }
+
+    #[test]
+    fn skips_what_isnt_a_discriminator() {
+        assert!(parse("bob#123 bob#12345 bob#0000 bob# #1234 b#1234").is_empty());
+    }
+
+    #[test]
+    fn counts_characters_rather_than_bytes() {
+        let found = parse("é héllo#0042");
+
+        assert_eq!(found[0].names[0], (2, "héllo".to_string()));
+        assert_eq!(found[0].end, 12);
+    }
+
+    #[test]
+    fn handles_dont_overlap() {
+        let found = parse("ann#0001bob#0002");
+
+        assert_eq!(found.len(), 2);
+        assert_eq!(names(&found[1]), vec!["bob", "ob"]);
+        assert_eq!(found[1].names[0].0, found[0].end);
+    }
+
+    #[test]
+    fn names_are_limited_in_length() {
+        let content = format!("{}#1234", "a".repeat(40));
+
+        assert_eq!(parse(&content)[0].names[0].1.chars().count(), MAX_USERNAME);
+    }
+
+    #[test]
+    fn mentions_are_limited_in_number() {
+        let content = "user#1234 ".repeat(MAX_MENTIONS + 10);
+
+        assert_eq!(parse(&content).len(), MAX_MENTIONS);
+    }
+}
@@ -0,0 +1,48 @@
+use crate::models;
+use crate::models::search::Keyed;
+
+#[derive(Debug, Serialize, Queryable)]
+pub struct Message {
+    #[serde(with = "models::id_serde")]
+    pub id: i64,
+    #[serde(with = "models::id_serde")]
+    pub sender: i64,
+    #[serde(with = "models::id_serde")]
+    pub recipient: i64,
+    pub created: chrono::NaiveDateTime,
+    pub content: String,
+    pub read: bool
+}
+
+impl Keyed for Message {
+    type Key = i64;
+
+    fn key(&self) -> i64 {
+        self.id
+    }
+}
+
+/// Someone the user has exchanged messages with, and the latest message between them.
+#[derive(Debug, Serialize, Queryable)]
+pub struct Conversation {
+    #[serde(with = "models::id_serde")]
+    pub user: i64,
+    pub username: String,
+    #[serde(with = "models::discriminator_serde")]
+    pub discriminator: i16,
+    #[serde(with = "models::id_serde")]
+    pub last_message: i64,
+    #[serde(with = "models::id_serde")]
+    pub last_sender: i64,
+    pub created: chrono::NaiveDateTime,
+    pub content: String,
+    pub unread: i64
+}
+
+impl Keyed for Conversation {
+    type Key = i64;
+
+    fn key(&self) -> i64 {
+        self.last_message
+    }
+}
@@ -1,5 +1,6 @@
 use diesel::{Queryable, sql_types::*};
 
+use crate::models::search::Keyed;
 use crate::models::visibility::Visibility;
 
 pub mod status;
@@ -7,6 +8,15 @@ pub mod visibility;
 pub mod profiles;
 pub mod entries;
 pub mod search;
+pub mod attachments;
+pub mod reactions;
+pub mod comments;
+pub mod mentions;
+pub mod notifications;
+pub mod digests;
+pub mod messages;
+pub mod webhooks;
+pub mod stats;
 
 sql_function! {
     fn can_see(me: Bigint, author: Bigint, journal: Bigint) -> Bool;
@@ -16,6 +26,78 @@ sql_function! {
     fn can_see_user(me: Bigint, other: Bigint) -> Bool;
 }
 
+sql_function! {
+    fn are_blocked(user1: Bigint, user2: Bigint) -> Bool;
+}
+
+sql_function! {
+    fn are_friends(user1: Bigint, user2: Bigint) -> Bool;
+}
+
+sql_function! {
+    fn id_generator() -> Bigint;
+}
+
+sql_function! {
+    fn edit_time_left(created: Timestamp, journal: Bigint) -> Nullable<Bigint>;
+}
+
+sql_function! {
+    /// Notifies a user unless they are the actor or have turned off this kind of notification.
+    fn notify(recipient: Bigint, what: notifications::db::NotificationKind, actor: Bigint, entry: Nullable<Bigint>, comment: Nullable<Bigint>) -> Nullable<Bigint>;
+}
+
+sql_function! {
+    /// The date a timestamp falls on in a timezone.
+    fn local_date(ts: Timestamp, tz: Text) -> Date;
+}
+
+sql_function! {
+    /// Whether a timestamp falls on or between two dates in a timezone.
+    fn created_between(ts: Timestamp, tz: Text, first: Date, last: Date) -> Bool;
+}
+
+sql_function! {
+    /// Whether a timestamp falls on today's month and day in an earlier year, in a timezone.
+    fn on_this_day(ts: Timestamp, tz: Text) -> Bool;
+}
+
+sql_function! {
+    fn word_count(body: Text) -> Integer;
+}
+
+sql_function! {
+    /// Whether a search document matches a search, which is parsed like a web search.
+    fn entry_matches(document: entries::db::TsVector, q: Text) -> Bool;
+}
+
+sql_function! {
+    /// How well an entry matches a search, highest first.
+    fn entry_rank(entry: Bigint, q: Text) -> Float;
+}
+
+sql_function! {
+    fn entry_snippet(content: Text, q: Text) -> Text;
+}
+
+sql_function! {
+    /// Whether a username starts with or looks like a search, or the summary does when summaries are searched too.
+    fn user_matches(name: Text, summary: Nullable<Text>, q: Text, summaries: Bool) -> Bool;
+}
+
+sql_function! {
+    /// How well a user matches a search, highest first and friends above everyone else.
+    fn user_score(me: Bigint, user: Bigint, name: Text, summary: Nullable<Text>, q: Text, summaries: Bool) -> Float;
+}
+
+sql_function! {
+    /// Replaces everything in a user's materialized feed, returning how many entries it now has.
+    fn rebuild_feed(me: Bigint) -> Bigint;
+}
+
+/// The number of hours entries in a new journal can be edited for, unless the owner chooses otherwise.
+pub const DEFAULT_EDIT_WINDOW: i32 = 24;
+
 #[derive(Debug, Serialize, Deserialize, Queryable)]
 pub struct Account {
     #[serde(with = "id_serde")]
@@ -41,7 +123,18 @@ pub struct Journal {
     pub modified: Option<chrono::NaiveDateTime>,
     pub description: Option<String>,
     pub visibility: Visibility,
-    pub color: i32
+    pub color: i32,
+    #[serde(with = "edit_window_serde")]
+    pub edit_window: Option<i32>,
+    pub comments_enabled: bool
+}
+
+impl Keyed for Journal {
+    type Key = i64;
+
+    fn key(&self) -> i64 {
+        self.id
+    }
 }
 
 pub mod id_serde {
@@ -88,6 +181,28 @@ pub mod id_serde {
     }
 }
 
+pub mod option_id_serde {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    struct Id(#[serde(with = "super::id_serde")] i64);
+
+    pub fn serialize<S>(val: &Option<i64>, ser: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+    {
+        match val {
+            Some(id) => super::id_serde::serialize(id, ser),
+            None => ser.serialize_none()
+        }
+    }
+
+    pub fn deserialize<'de, D>(de: D) -> Result<Option<i64>, D::Error>
+        where D: Deserializer<'de> {
+        Option::<Id>::deserialize(de).map(|id| id.map(|Id(id)| id))
+    }
+}
+
 pub mod discriminator_serde {
     use std::fmt;
 
@@ -130,4 +245,100 @@ pub mod discriminator_serde {
         where D: Deserializer<'de> {
         de.deserialize_str(Vis)
     }
-}
\ No newline at end of file
+}
+
+/// Edit windows are stored as a number of hours, with `0` meaning entries can never be edited
+/// and `null` meaning they can always be edited.
+pub mod edit_window_serde {
+    use std::convert::TryFrom;
+    use std::fmt;
+
+    use serde::{de, Deserializer, ser, Serializer};
+    use serde::de::{Unexpected, Visitor};
+
+    pub fn serialize<S>(val: &Option<i32>, ser: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer
+    {
+        match *val {
+            None => ser.serialize_str("always"),
+            Some(0) => ser.serialize_str("never"),
+            Some(hours) if hours > 0 => ser.serialize_i32(hours),
+            Some(_) => Err(ser::Error::custom(format!("expected value >= 0"))),
+        }
+    }
+
+    struct Vis;
+
+    impl<'de> Visitor<'de> for Vis {
+        type Value = Option<i32>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("\"never\", \"always\" or a positive number of hours")
+        }
+
+        fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+        {
+            match value {
+                "never" => Ok(Some(0)),
+                "always" => Ok(None),
+                _ => Err(E::invalid_value(Unexpected::Str(value), &self))
+            }
+        }
+
+        fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+        {
+            i32::try_from(value)
+                .map(Some)
+                .map_err(|_| E::invalid_value(Unexpected::Unsigned(value), &self))
+        }
+
+        fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+        {
+            if value < 0 {
+                Err(E::invalid_value(Unexpected::Signed(value), &self))
+            } else {
+                self.visit_u64(value as u64)
+            }
+        }
+    }
+
+    pub fn deserialize<'de, D>(de: D) -> Result<Option<i32>, D::Error>
+        where D: Deserializer<'de> {
+        de.deserialize_any(Vis)
+    }
+
+    /// For optional fields, where leaving the field out should be distinguishable from `"always"`.
+    pub fn deserialize_some<'de, D>(de: D) -> Result<Option<Option<i32>>, D::Error>
+        where D: Deserializer<'de> {
+        deserialize(de).map(Some)
+    }
+}
+
+/// Deserializes a comma separated query parameter, such as `?tags=travel,food`.
+pub mod comma_serde {
+    use std::fmt::Display;
+    use std::str::FromStr;
+
+    use serde::{de, Deserialize, Deserializer};
+
+    pub fn deserialize<'de, D, T>(de: D) -> Result<Vec<T>, D::Error>
+        where
+            D: Deserializer<'de>,
+            T: FromStr,
+            T::Err: Display
+    {
+        String::deserialize(de)?
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse().map_err(de::Error::custom))
+            .collect()
+    }
+}
@@ -0,0 +1,92 @@
+use std::io::Write;
+
+use diesel::{deserialize, serialize};
+use diesel::deserialize::FromSql;
+use diesel::pg::Pg;
+use diesel::serialize::{IsNull, Output, ToSql};
+
+use crate::models;
+use crate::models::search::Keyed;
+
+pub mod db {
+    #[derive(SqlType, QueryId)]
+    #[postgres(type_name = "notification_kind")]
+    pub struct NotificationKind;
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, FromSqlRow, AsExpression)]
+#[sql_type = "db::NotificationKind"]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    FriendRequest,
+    FriendAccepted,
+    Reaction,
+    Comment,
+    Reply,
+    Mention
+}
+
+impl NotificationKind {
+    pub const ALL: [NotificationKind; 6] = [
+        NotificationKind::FriendRequest,
+        NotificationKind::FriendAccepted,
+        NotificationKind::Reaction,
+        NotificationKind::Comment,
+        NotificationKind::Reply,
+        NotificationKind::Mention
+    ];
+}
+
+#[derive(Debug, Serialize, Queryable)]
+pub struct Notification {
+    #[serde(with = "models::id_serde")]
+    pub id: i64,
+    pub kind: NotificationKind,
+    /// The user whose action caused the notification.
+    #[serde(with = "models::id_serde")]
+    pub actor: i64,
+    #[serde(skip_serializing_if = "Option::is_none", with = "models::option_id_serde")]
+    pub entry: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none", with = "models::option_id_serde")]
+    pub journal: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none", with = "models::option_id_serde")]
+    pub comment: Option<i64>,
+    pub created: chrono::NaiveDateTime,
+    pub read: bool
+}
+
+impl Keyed for Notification {
+    type Key = i64;
+
+    fn key(&self) -> i64 {
+        self.id
+    }
+}
+
+impl ToSql<db::NotificationKind, Pg> for NotificationKind {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, Pg>) -> serialize::Result {
+        match *self {
+            NotificationKind::FriendRequest => out.write_all(b"friend_request")?,
+            NotificationKind::FriendAccepted => out.write_all(b"friend_accepted")?,
+            NotificationKind::Reaction => out.write_all(b"reaction")?,
+            NotificationKind::Comment => out.write_all(b"comment")?,
+            NotificationKind::Reply => out.write_all(b"reply")?,
+            NotificationKind::Mention => out.write_all(b"mention")?,
+        }
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<db::NotificationKind, Pg> for NotificationKind {
+    fn from_sql(bytes: Option<&[u8]>) -> deserialize::Result<Self> {
+        match not_none!(bytes) {
+            b"friend_request" => Ok(NotificationKind::FriendRequest),
+            b"friend_accepted" => Ok(NotificationKind::FriendAccepted),
+            b"reaction" => Ok(NotificationKind::Reaction),
+            b"comment" => Ok(NotificationKind::Comment),
+            b"reply" => Ok(NotificationKind::Reply),
+            b"mention" => Ok(NotificationKind::Mention),
+            _ => Err("Unrecognized enum variant".into()),
+        }
+    }
+}
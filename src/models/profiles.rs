@@ -1,6 +1,21 @@
 use diesel::{Queryable, sql_types::*};
 
 use crate::models::{self, visibility::{db, Visibility}};
+use crate::models::search::Keyed;
+
+/// The square sizes avatars are stored in, largest first.
+pub const AVATAR_SIZES: &[u32] = &[512, 128, 48];
+
+pub const DEFAULT_AVATAR_SIZE: u32 = 128;
+
+/// The size banners are cropped to fill.
+pub const BANNER_SIZE: (u32, u32) = (1500, 500);
+
+/// The largest avatar or banner that can be uploaded, in bytes.
+pub const MAX_IMAGE_SIZE: usize = 8 * 1024 * 1024;
+
+/// The longest a search for users can be, in characters, which is as long as a summary.
+pub const MAX_SEARCH_LENGTH: usize = 120;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Username {
@@ -18,6 +33,10 @@ pub struct Profile {
     pub summary: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub bio: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub banner: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -29,6 +48,9 @@ pub struct FullProfile {
     pub modified: Option<chrono::NaiveDateTime>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub username_modified: Option<chrono::NaiveDateTime>,
+    /// Only shown to the user themselves.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -39,7 +61,7 @@ pub struct Friend {
 
 impl Profile {
     #[inline(always)]
-    pub fn new(userid: i64, username: String, discriminator: i16, summary: Option<String>, bio: Option<String>) -> Self {
+    pub fn new(userid: i64, username: String, discriminator: i16, summary: Option<String>, bio: Option<String>, avatar: Option<i64>, banner: Option<i64>) -> Self {
         Profile {
             userid,
             username: Username {
@@ -47,40 +69,79 @@ impl Profile {
                 discriminator
             },
             summary,
-            bio
+            bio,
+            avatar: avatar.map(|version| format!("/user/{:019}/avatar?v={:019}", userid, version)),
+            banner: banner.map(|version| format!("/user/{:019}/banner?v={:019}", userid, version)),
         }
     }
 }
 
-impl Queryable<(BigInt, Text, SmallInt, Nullable<Text>, Nullable<Text>, db::Visibility, Timestamp, Nullable<Timestamp>, Nullable<Timestamp>), diesel::pg::Pg> for FullProfile {
-    type Row = (i64, String, i16, Option<String>, Option<String>, Visibility, chrono::NaiveDateTime, Option<chrono::NaiveDateTime>, Option<chrono::NaiveDateTime>);
+#[inline(always)]
+pub fn avatar_key(userid: i64, version: i64, size: u32) -> String {
+    format!("avatars/{:019}/{:019}/{}.png", userid, version, size)
+}
+
+#[inline(always)]
+pub fn banner_key(userid: i64, version: i64) -> String {
+    format!("banners/{:019}/{:019}.png", userid, version)
+}
+
+impl Queryable<(BigInt, Text, SmallInt, Nullable<Text>, Nullable<Text>, db::Visibility, Timestamp, Nullable<Timestamp>, Nullable<Timestamp>, Nullable<BigInt>, Nullable<BigInt>, Text), diesel::pg::Pg> for FullProfile {
+    type Row = (i64, String, i16, Option<String>, Option<String>, Visibility, chrono::NaiveDateTime, Option<chrono::NaiveDateTime>, Option<chrono::NaiveDateTime>, Option<i64>, Option<i64>, String);
 
     fn build(row: Self::Row) -> Self {
         FullProfile {
-            profile: Profile::new(row.0, row.1, row.2, row.3, row.4),
+            profile: Profile::new(row.0, row.1, row.2, row.3, row.4, row.9, row.10),
             visibility: row.5,
             created: row.6,
             modified: row.7,
-            username_modified: row.8
+            username_modified: row.8,
+            timezone: Some(row.11)
         }
     }
 }
 
-impl Queryable<(BigInt, Text, SmallInt, Nullable<Text>, Nullable<Text>, Timestamp), diesel::pg::Pg> for Friend {
-    type Row = (i64, String, i16, Option<String>, Option<String>, chrono::NaiveDateTime);
+impl Queryable<(BigInt, Text, SmallInt, Nullable<Text>, Nullable<Text>, Timestamp, Nullable<BigInt>, Nullable<BigInt>), diesel::pg::Pg> for Friend {
+    type Row = (i64, String, i16, Option<String>, Option<String>, chrono::NaiveDateTime, Option<i64>, Option<i64>);
 
     fn build(row: Self::Row) -> Self {
         Friend {
-            with: Profile::new(row.0, row.1, row.2, row.3, row.4),
+            with: Profile::new(row.0, row.1, row.2, row.3, row.4, row.6, row.7),
             since: row.5
         }
     }
 }
 
-impl Queryable<(BigInt, Text, SmallInt, Nullable<Text>, Nullable<Text>), diesel::pg::Pg> for Profile {
-    type Row = (i64, String, i16, Option<String>, Option<String>);
+/// A user found by searching, along with how well they matched.
+#[derive(Debug, Serialize)]
+pub struct Found {
+    #[serde(flatten)]
+    pub profile: Profile,
+    pub score: f32,
+    pub friend: bool
+}
+
+impl Keyed for Found {
+    type Key = (u32, i64);
+
+    fn key(&self) -> Self::Key {
+        (self.score.to_bits(), self.profile.userid)
+    }
+}
+
+/// Friends are listed by how long they've been friends.
+impl Keyed for Friend {
+    type Key = (chrono::NaiveDateTime, i64);
+
+    fn key(&self) -> Self::Key {
+        (self.since, self.with.userid)
+    }
+}
+
+impl Queryable<(BigInt, Text, SmallInt, Nullable<Text>, Nullable<Text>, Nullable<BigInt>, Nullable<BigInt>), diesel::pg::Pg> for Profile {
+    type Row = (i64, String, i16, Option<String>, Option<String>, Option<i64>, Option<i64>);
 
     fn build(row: Self::Row) -> Self {
-        Profile::new(row.0, row.1, row.2, row.3, row.4)
+        Profile::new(row.0, row.1, row.2, row.3, row.4, row.5, row.6)
     }
-}
\ No newline at end of file
+}
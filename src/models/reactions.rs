@@ -0,0 +1,60 @@
+use std::io::Write;
+
+use diesel::{deserialize, serialize};
+use diesel::deserialize::FromSql;
+use diesel::pg::Pg;
+use diesel::serialize::{IsNull, Output, ToSql};
+
+pub mod db {
+    #[derive(SqlType, QueryId)]
+    #[postgres(type_name = "reaction")]
+    pub struct Reaction;
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, FromSqlRow, AsExpression)]
+#[sql_type = "db::Reaction"]
+#[serde(rename_all = "lowercase")]
+pub enum Reaction {
+    Heart,
+    Laugh,
+    Wow,
+    Sad,
+    Hug,
+    Clap
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReactionCount {
+    pub reaction: Reaction,
+    pub count: i64,
+    /// Whether the user viewing the entry left this reaction.
+    pub reacted: bool
+}
+
+impl ToSql<db::Reaction, Pg> for Reaction {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, Pg>) -> serialize::Result {
+        match *self {
+            Reaction::Heart => out.write_all(b"heart")?,
+            Reaction::Laugh => out.write_all(b"laugh")?,
+            Reaction::Wow => out.write_all(b"wow")?,
+            Reaction::Sad => out.write_all(b"sad")?,
+            Reaction::Hug => out.write_all(b"hug")?,
+            Reaction::Clap => out.write_all(b"clap")?,
+        }
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<db::Reaction, Pg> for Reaction {
+    fn from_sql(bytes: Option<&[u8]>) -> deserialize::Result<Self> {
+        match not_none!(bytes) {
+            b"heart" => Ok(Reaction::Heart),
+            b"laugh" => Ok(Reaction::Laugh),
+            b"wow" => Ok(Reaction::Wow),
+            b"sad" => Ok(Reaction::Sad),
+            b"hug" => Ok(Reaction::Hug),
+            b"clap" => Ok(Reaction::Clap),
+            _ => Err("Unrecognized enum variant".into()),
+        }
+    }
+}
@@ -1,21 +1,54 @@
 use std::cmp::min;
 
+use actix_web::HttpRequest;
+use chrono::{NaiveDate, NaiveDateTime};
+use hmac::{Hmac, Mac};
+use serde::de::DeserializeOwned;
 use serde::Serialize;
+use sha2::Sha256;
 
-#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+use crate::errors::{Error, ValyouResult};
+
+/// Cursors are signed with the session secret, under a prefix of their own so that
+/// nothing else signed with it can pass for a cursor.
+static SECRET: &'static str = dotenv!("JWT_SECRET");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum SearchMethod {
     Before,
     After
 }
 
+/// Clients either start paging from a bare snowflake, as lists always used to work,
+/// or hand back a cursor from a previous page. Without either, the list starts at the beginning.
 #[derive(Debug, Deserialize)]
 pub struct SearchQuery {
-    pub id: i64,
+    pub id: Option<i64>,
+    pub cursor: Option<String>,
     #[serde(default = "default_limit")]
     pub limit: i64
 }
 
+/// Where a page starts and how many values to fetch for it.
+#[derive(Debug)]
+pub struct Page<K> {
+    pub method: SearchMethod,
+    /// Only values past this key in the direction of the method are on the page.
+    pub key: K,
+    /// One more than are returned, so that `Paginated::paginate` can tell whether there are more.
+    pub limit: i64
+}
+
+/// A position in a list, encoded as url safe base64 of the JSON and its signature.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Cursor<K> {
+    #[serde(rename = "m")]
+    pub method: SearchMethod,
+    #[serde(rename = "k")]
+    pub key: K
+}
+
 #[derive(Debug, Serialize)]
 #[serde(bound(serialize = "T: Serialize"))]
 pub struct Paginated<T> {
@@ -25,25 +58,313 @@ pub struct Paginated<T> {
 
 #[derive(Debug, Serialize)]
 pub struct Pagination {
-    pub next_url: String
+    /// Continues in the same direction from the last value, which is worth polling
+    /// for newer values even when there weren't any more yet.
+    pub next: Option<String>,
+    /// Turns around and goes the other way from the first value.
+    pub prev: Option<String>,
+    /// Whether there was anything past the last value when the page was fetched.
+    pub has_more: bool
+}
+
+/// The tuple of columns a list is sorted by, with the value's snowflake last to break ties.
+pub trait SortKey: Serialize + DeserializeOwned {
+    /// A key that comes before every value when going in this direction.
+    fn start(method: SearchMethod) -> Self;
+
+    /// Lists sorted by a snowflake alone also accept a bare `?id=`.
+    fn from_id(_id: i64) -> Option<Self> {
+        None
+    }
+}
+
+/// Values that can be paged through.
+pub trait Keyed {
+    type Key: SortKey;
+
+    /// The key that the next page is fetched relative to.
+    fn key(&self) -> Self::Key;
+}
+
+impl SortKey for i64 {
+    fn start(method: SearchMethod) -> Self {
+        match method {
+            SearchMethod::Before => std::i64::MAX,
+            SearchMethod::After => std::i64::MIN
+        }
+    }
+
+    fn from_id(id: i64) -> Option<Self> {
+        Some(id)
+    }
+}
+
+impl SortKey for (NaiveDateTime, i64) {
+    fn start(method: SearchMethod) -> Self {
+        match method {
+            SearchMethod::Before => (NaiveDate::from_ymd(9999, 12, 31).and_hms(23, 59, 59), std::i64::MAX),
+            SearchMethod::After => (NaiveDate::from_ymd(1, 1, 1).and_hms(0, 0, 0), std::i64::MIN)
+        }
+    }
+}
+
+/// Ranked lists carry the time they were first ranked at, which is only missing before the first page.
+/// The score is kept as its bits, since serde_json doesn't always parse floats back exactly.
+impl SortKey for (Option<NaiveDateTime>, u64, i64) {
+    fn start(method: SearchMethod) -> Self {
+        match method {
+            SearchMethod::Before => (None, std::f64::MAX.to_bits(), std::i64::MAX),
+            SearchMethod::After => (None, std::f64::MIN.to_bits(), std::i64::MIN)
+        }
+    }
+}
+
+/// Search results are sorted by how well they match, kept as the bits of the `f32` rank.
+impl SortKey for (u32, i64) {
+    fn start(method: SearchMethod) -> Self {
+        match method {
+            SearchMethod::Before => (std::f32::MAX.to_bits(), std::i64::MAX),
+            SearchMethod::After => (std::f32::MIN.to_bits(), std::i64::MIN)
+        }
+    }
+}
+
+impl SearchMethod {
+    pub fn name(self) -> &'static str {
+        match self {
+            SearchMethod::Before => "before",
+            SearchMethod::After => "after"
+        }
+    }
+
+    pub fn reverse(self) -> Self {
+        match self {
+            SearchMethod::Before => SearchMethod::After,
+            SearchMethod::After => SearchMethod::Before
+        }
+    }
 }
 
 impl SearchQuery {
-    pub fn into_parts(self) -> (i64, i64) {
-        (if self.id < 0 { std::i64::MAX } else { self.id }, min(self.limit, 30))
+    /// A cursor carries its own direction, which takes the place of the one in the route.
+    /// Negative ids are treated as no id at all.
+    pub fn page<K: SortKey>(self, method: SearchMethod) -> ValyouResult<Page<K>> {
+        let limit = min(self.limit, 30) + 1;
+
+        if let Some(cursor) = self.cursor {
+            let Cursor { method, key } = Cursor::decode(&cursor)?;
+
+            return Ok(Page { method, key, limit });
+        }
+
+        let key = match self.id.filter(|&id| id >= 0) {
+            Some(id) => K::from_id(id).ok_or_else(|| Error::BadRequest("this list can only be paged with a cursor".into()))?,
+            None => K::start(method)
+        };
+
+        Ok(Page { method, key, limit })
     }
 }
 
-impl<T> Paginated<T> {
-    pub fn paginate(values: Vec<T>, method: SearchMethod) -> Self {
+impl<K: SortKey> Cursor<K> {
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).unwrap();
+        let signature = mac(&json).result().code();
+
+        format!("{}.{}", base64::encode_config(&json, base64::URL_SAFE_NO_PAD), base64::encode_config(&signature, base64::URL_SAFE_NO_PAD))
+    }
+
+    pub fn decode(cursor: &str) -> ValyouResult<Self> {
+        let invalid = || Error::BadRequest("invalid cursor".into());
+
+        let mut parts = cursor.splitn(2, '.');
+        let json = parts.next().and_then(|p| base64::decode_config(p, base64::URL_SAFE_NO_PAD).ok()).ok_or_else(invalid)?;
+        let signature = parts.next().and_then(|p| base64::decode_config(p, base64::URL_SAFE_NO_PAD).ok()).ok_or_else(invalid)?;
+
+        mac(&json).verify(&signature).map_err(|_| invalid())?;
+
+        serde_json::from_slice(&json).map_err(|_| invalid())
+    }
+}
+
+fn mac(json: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_varkey(SECRET.as_bytes()).expect("hmac accepts keys of any length");
+    mac.input(b"cursor:");
+    mac.input(json);
+    mac
+}
+
+impl<T: Keyed> Paginated<T> {
+    /// Pages values fetched for a `Page`, linking back to the route that was requested.
+    pub fn paginate(values: Vec<T>, method: SearchMethod, fetched: i64, req: &HttpRequest) -> Self {
+        let base = match req.path().rfind('/') {
+            Some(i) => &req.path()[..i],
+            None => ""
+        };
+
+        Self::paginate_at(values, method, fetched, base, req.query_string())
+    }
+
+    /// Pages values for a route other than the one being requested, such as the journals on a profile.
+    /// The base is the route without its method, and the query is kept apart from the cursor.
+    pub fn paginate_at(mut values: Vec<T>, method: SearchMethod, fetched: i64, base: &str, query: &str) -> Self {
+        let has_more = values.len() as i64 >= fetched;
+        values.truncate((fetched - 1).max(0) as usize);
+
+        let link = |method: SearchMethod, key: T::Key| {
+            let mut params: Vec<(String, String)> = serde_urlencoded::from_str(query).unwrap_or_default();
+            params.retain(|(k, _)| k != "id" && k != "cursor");
+            params.insert(0, ("cursor".into(), Cursor { method, key }.encode()));
+
+            format!("{}/{}?{}", base, method.name(), serde_urlencoded::to_string(params).unwrap())
+        };
+
+        let next = values.last().map(|v| link(method, v.key()));
+        let prev = values.first().map(|v| link(method.reverse(), v.key()));
+
         Paginated {
             values,
-            pagination: Pagination {
-                next_url: serde_json::to_string(&method).unwrap()
-            }
+            pagination: Pagination { next, prev, has_more }
         }
     }
 }
 
 #[inline(always)]
-const fn default_limit() -> i64 { 20 }
\ No newline at end of file
+pub const fn default_limit() -> i64 { 20 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Item(i64);
+
+    impl Keyed for Item {
+        type Key = i64;
+
+        fn key(&self) -> i64 {
+            self.0
+        }
+    }
+
+    fn query(id: Option<i64>, cursor: Option<String>, limit: i64) -> SearchQuery {
+        SearchQuery { id, cursor, limit }
+    }
+
+    fn cursor_of(link: &str) -> String {
+        let query = &link[link.find('?').unwrap() + 1..];
+        let params: Vec<(String, String)> = serde_urlencoded::from_str(query).unwrap();
+
+        params.into_iter().find(|(k, _)| k == "cursor").unwrap().1
+    }
+
+    #[test]
+    fn cursors_round_trip() {
+        let key = (Some(NaiveDate::from_ymd(2020, 3, 1).and_hms(12, 0, 0)), 0.1f64.to_bits(), 42);
+        let decoded = Cursor::<(Option<NaiveDateTime>, u64, i64)>::decode(&Cursor { method: SearchMethod::After, key }.encode()).unwrap();
+
+        assert_eq!(decoded.method, SearchMethod::After);
+        assert_eq!(decoded.key, key);
+    }
+
+    #[test]
+    fn tampered_cursors_are_rejected() {
+        let cursor = Cursor { method: SearchMethod::Before, key: 10i64 }.encode();
+        let other = Cursor { method: SearchMethod::Before, key: 11i64 }.encode();
+
+        let (json, signature) = cursor.split_at(cursor.find('.').unwrap());
+        let (other_json, other_signature) = other.split_at(other.find('.').unwrap());
+
+        assert!(Cursor::<i64>::decode(&format!("{}{}", other_json, signature)).is_err());
+        assert!(Cursor::<i64>::decode(&format!("{}{}", json, other_signature)).is_err());
+
+        let forged = base64::encode_config(br#"{"m":"before","k":11}"#, base64::URL_SAFE_NO_PAD);
+        assert!(Cursor::<i64>::decode(&format!("{}{}", forged, signature)).is_err());
+    }
+
+    #[test]
+    fn truncated_cursors_are_rejected() {
+        let cursor = Cursor { method: SearchMethod::Before, key: 10i64 }.encode();
+        let dot = cursor.find('.').unwrap();
+
+        assert!(Cursor::<i64>::decode(&cursor[..cursor.len() - 4]).is_err());
+        assert!(Cursor::<i64>::decode(&cursor[..dot]).is_err());
+        assert!(Cursor::<i64>::decode(&cursor[..dot + 1]).is_err());
+        assert!(Cursor::<i64>::decode("").is_err());
+    }
+
+    #[test]
+    fn cursors_only_decode_to_their_own_key() {
+        let cursor = Cursor { method: SearchMethod::Before, key: 10i64 }.encode();
+
+        assert!(Cursor::<(u32, i64)>::decode(&cursor).is_err());
+    }
+
+    #[test]
+    fn start_comes_before_every_value() {
+        assert_eq!(i64::start(SearchMethod::Before), std::i64::MAX);
+        assert_eq!(i64::start(SearchMethod::After), std::i64::MIN);
+
+        assert_eq!(<(u32, i64)>::start(SearchMethod::Before), (std::f32::MAX.to_bits(), std::i64::MAX));
+        assert_eq!(<(Option<NaiveDateTime>, u64, i64)>::start(SearchMethod::After), (None, std::f64::MIN.to_bits(), std::i64::MIN));
+
+        let (before, _) = <(NaiveDateTime, i64)>::start(SearchMethod::Before);
+        let (after, _) = <(NaiveDateTime, i64)>::start(SearchMethod::After);
+        assert!(after < before);
+    }
+
+    #[test]
+    fn pages_start_from_an_id_or_the_start() {
+        let page = query(None, None, 20).page::<i64>(SearchMethod::Before).unwrap();
+        assert_eq!((page.method, page.key, page.limit), (SearchMethod::Before, std::i64::MAX, 21));
+
+        let page = query(Some(5), None, 100).page::<i64>(SearchMethod::After).unwrap();
+        assert_eq!((page.key, page.limit), (5, 31));
+
+        let page = query(Some(-1), None, 20).page::<i64>(SearchMethod::After).unwrap();
+        assert_eq!(page.key, std::i64::MIN);
+
+        assert!(query(Some(5), None, 20).page::<(u32, i64)>(SearchMethod::Before).is_err());
+    }
+
+    #[test]
+    fn cursors_take_the_place_of_the_route_method() {
+        let cursor = Cursor { method: SearchMethod::After, key: 7i64 }.encode();
+        let page = query(Some(5), Some(cursor), 20).page::<i64>(SearchMethod::Before).unwrap();
+
+        assert_eq!((page.method, page.key), (SearchMethod::After, 7));
+    }
+
+    #[test]
+    fn paginate_at_links_from_the_ends_of_the_page() {
+        let values = vec![Item(9), Item(8), Item(7)];
+        let page = Paginated::paginate_at(values, SearchMethod::Before, 3, "/journals/1", "id=50&tag=rust");
+
+        assert_eq!(page.values.iter().map(|v| v.0).collect::<Vec<_>>(), vec![9, 8]);
+        assert!(page.pagination.has_more);
+
+        let next = page.pagination.next.unwrap();
+        assert!(next.starts_with("/journals/1/before?cursor="));
+        assert!(next.ends_with("&tag=rust"));
+        assert!(!next.contains("id=50"));
+
+        let next = Cursor::<i64>::decode(&cursor_of(&next)).unwrap();
+        assert_eq!((next.method, next.key), (SearchMethod::Before, 8));
+
+        let prev = page.pagination.prev.unwrap();
+        assert!(prev.starts_with("/journals/1/after?cursor="));
+
+        let prev = Cursor::<i64>::decode(&cursor_of(&prev)).unwrap();
+        assert_eq!((prev.method, prev.key), (SearchMethod::After, 9));
+    }
+
+    #[test]
+    fn paginate_at_without_more_values() {
+        let page = Paginated::paginate_at(vec![Item(1)], SearchMethod::After, 3, "", "");
+        assert!(!page.pagination.has_more);
+        assert_eq!(page.values.len(), 1);
+
+        let empty = Paginated::<Item>::paginate_at(vec![], SearchMethod::After, 3, "", "");
+        assert!(!empty.pagination.has_more);
+        assert!(empty.pagination.next.is_none() && empty.pagination.prev.is_none());
+    }
+}
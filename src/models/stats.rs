@@ -0,0 +1,238 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Duration, NaiveDate};
+
+/// How many tags are listed in stats.
+pub const TOP_TAGS: i64 = 10;
+
+/// How far back stats look from today.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Window {
+    Week,
+    Month,
+    Year,
+    All
+}
+
+/// How entries are grouped for significance over time.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    Day,
+    Week,
+    Month
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Stats {
+    /// The day in the user's timezone that streaks were counted up to.
+    pub as_of: NaiveDate,
+    pub entries: i64,
+    pub words: i64,
+    pub days_written: i64,
+    /// Consecutive days written up to today, or up to yesterday while today is still blank.
+    pub current_streak: i64,
+    pub longest_streak: i64,
+    pub significance: Vec<PeriodStats>,
+    pub top_tags: Vec<TagCount>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PeriodStats {
+    pub start: NaiveDate,
+    pub entries: i64,
+    /// Left out when none of the entries had a significance.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub average_significance: Option<f64>
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable)]
+pub struct TagCount {
+    pub tag: String,
+    pub entries: i64
+}
+
+impl Default for Window {
+    fn default() -> Self {
+        Window::All
+    }
+}
+
+impl Default for Period {
+    fn default() -> Self {
+        Period::Month
+    }
+}
+
+impl Window {
+    pub fn name(self) -> &'static str {
+        match self {
+            Window::Week => "week",
+            Window::Month => "month",
+            Window::Year => "year",
+            Window::All => "all"
+        }
+    }
+
+    /// The first day in the window, which includes today.
+    pub fn start(self, today: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            Window::Week => Some(today - Duration::days(6)),
+            Window::Month => Some(today - Duration::days(29)),
+            Window::Year => Some(today - Duration::days(364)),
+            Window::All => None
+        }
+    }
+}
+
+impl Period {
+    pub fn name(self) -> &'static str {
+        match self {
+            Period::Day => "day",
+            Period::Week => "week",
+            Period::Month => "month"
+        }
+    }
+
+    /// The first day of the period a day falls in, with weeks starting on monday.
+    pub fn start(self, day: NaiveDate) -> NaiveDate {
+        match self {
+            Period::Day => day,
+            Period::Week => day - Duration::days(day.weekday().num_days_from_monday() as i64),
+            Period::Month => day.with_day(1).unwrap()
+        }
+    }
+}
+
+impl Stats {
+    /// Works out stats from the local date, word count and significance of each entry.
+    pub fn new(written: Vec<(NaiveDate, i32, Option<f64>)>, top_tags: Vec<TagCount>, today: NaiveDate, period: Period) -> Self {
+        let entries = written.len() as i64;
+        let words = written.iter().map(|&(_, words, _)| words as i64).sum();
+
+        let mut days: Vec<NaiveDate> = written.iter().map(|&(day, _, _)| day).collect();
+        days.sort();
+        days.dedup();
+
+        let mut longest_streak = 0;
+        let mut streak = 0;
+        let mut previous: Option<NaiveDate> = None;
+
+        for &day in &days {
+            streak = match previous {
+                Some(previous) if previous.succ() == day => streak + 1,
+                _ => 1
+            };
+
+            longest_streak = longest_streak.max(streak);
+            previous = Some(day);
+        }
+
+        let current_streak = match days.last() {
+            Some(&last) if last == today || last.succ() == today => streak,
+            _ => 0
+        };
+
+        // entries and the sum and count of their significances in each period
+        let mut periods: BTreeMap<NaiveDate, (i64, f64, i64)> = BTreeMap::new();
+
+        for &(day, _, significance) in &written {
+            let totals = periods.entry(period.start(day)).or_default();
+            totals.0 += 1;
+
+            if let Some(significance) = significance {
+                totals.1 += significance;
+                totals.2 += 1;
+            }
+        }
+
+        let significance = periods.into_iter()
+            .map(|(start, (entries, sum, rated))| PeriodStats {
+                start,
+                entries,
+                average_significance: if rated > 0 { Some(sum / rated as f64) } else { None }
+            })
+            .collect();
+
+        Stats {
+            as_of: today,
+            entries,
+            words,
+            days_written: days.len() as i64,
+            current_streak,
+            longest_streak,
+            significance,
+            top_tags
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd(2020, month, day)
+    }
+
+    fn on(days: &[NaiveDate]) -> Vec<(NaiveDate, i32, Option<f64>)> {
+        days.iter().map(|&day| (day, 10, None)).collect()
+    }
+
+    fn streaks(days: &[NaiveDate], today: NaiveDate) -> (i64, i64) {
+        let stats = Stats::new(on(days), vec![], today, Period::Day);
+
+        (stats.current_streak, stats.longest_streak)
+    }
+
+    #[test]
+    fn streak_ending_today() {
+        assert_eq!(streaks(&[date(3, 8), date(3, 9), date(3, 10)], date(3, 10)), (3, 3));
+    }
+
+    #[test]
+    fn streak_ending_yesterday_is_still_current() {
+        assert_eq!(streaks(&[date(3, 8), date(3, 9)], date(3, 10)), (2, 2));
+    }
+
+    #[test]
+    fn streak_ending_earlier_is_over() {
+        assert_eq!(streaks(&[date(3, 6), date(3, 7), date(3, 8)], date(3, 10)), (0, 3));
+    }
+
+    #[test]
+    fn longest_streak_can_be_in_the_past() {
+        let days = [date(2, 27), date(2, 28), date(2, 29), date(3, 1), date(3, 5), date(3, 6)];
+
+        assert_eq!(streaks(&days, date(3, 6)), (2, 4));
+    }
+
+    #[test]
+    fn several_entries_on_a_day_count_once() {
+        let stats = Stats::new(on(&[date(3, 9), date(3, 9), date(3, 10)]), vec![], date(3, 10), Period::Day);
+
+        assert_eq!((stats.entries, stats.words, stats.days_written), (3, 30, 2));
+        assert_eq!((stats.current_streak, stats.longest_streak), (2, 2));
+    }
+
+    #[test]
+    fn nothing_written() {
+        let stats = Stats::new(vec![], vec![], date(3, 10), Period::Month);
+
+        assert_eq!((stats.entries, stats.days_written, stats.current_streak, stats.longest_streak), (0, 0, 0, 0));
+        assert!(stats.significance.is_empty());
+    }
+
+    #[test]
+    fn significance_is_averaged_per_period() {
+        let written = vec![(date(3, 2), 5, Some(1.0)), (date(3, 8), 5, Some(3.0)), (date(3, 9), 5, None), (date(4, 1), 5, None)];
+        let stats = Stats::new(written, vec![], date(4, 1), Period::Week);
+
+        let periods: Vec<(NaiveDate, i64, Option<f64>)> = stats.significance.iter()
+            .map(|p| (p.start, p.entries, p.average_significance))
+            .collect();
+
+        assert_eq!(periods, vec![(date(3, 2), 2, Some(2.0)), (date(3, 9), 1, None), (date(3, 30), 1, None)]);
+    }
+}
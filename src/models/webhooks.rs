@@ -0,0 +1,287 @@
+use std::io::Write;
+use std::net::{IpAddr, Ipv4Addr, ToSocketAddrs};
+
+use diesel::{deserialize, Queryable, serialize, sql_types::*};
+use diesel::deserialize::FromSql;
+use diesel::pg::Pg;
+use diesel::serialize::{IsNull, Output, ToSql};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use url::{Host, Url};
+
+use crate::models;
+use crate::models::search::Keyed;
+
+/// The most webhooks one user can register.
+pub const MAX_WEBHOOKS: i64 = 10;
+
+/// Deliveries are abandoned after failing this many times.
+pub const MAX_ATTEMPTS: i32 = 8;
+
+/// How many seconds to wait after the first failed attempt, doubling with each one after.
+pub const RETRY_DELAY: i64 = 30;
+
+pub mod db {
+    #[derive(SqlType, QueryId)]
+    #[postgres(type_name = "webhook_event")]
+    pub struct WebhookEvent;
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, FromSqlRow, AsExpression)]
+#[sql_type = "db::WebhookEvent"]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    EntryCreated,
+    FriendRequest,
+    JournalUpdated,
+    /// Only sent when the owner asks for a test delivery.
+    Ping
+}
+
+impl WebhookEvent {
+    pub fn name(self) -> &'static str {
+        match self {
+            WebhookEvent::EntryCreated => "entry_created",
+            WebhookEvent::FriendRequest => "friend_request",
+            WebhookEvent::JournalUpdated => "journal_updated",
+            WebhookEvent::Ping => "ping",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Webhook {
+    #[serde(with = "models::id_serde")]
+    pub id: i64,
+    #[serde(skip)]
+    pub owner: i64,
+    pub url: String,
+    /// Only shown when the webhook is created.
+    #[serde(skip)]
+    pub secret: String,
+    pub active: bool,
+    pub created: chrono::NaiveDateTime,
+    pub events: Vec<WebhookEvent>
+}
+
+impl Queryable<(BigInt, BigInt, Text, Text, Bool, Timestamp), Pg> for Webhook {
+    type Row = (i64, i64, String, String, bool, chrono::NaiveDateTime);
+
+    fn build(row: Self::Row) -> Self {
+        Webhook {
+            id: row.0,
+            owner: row.1,
+            url: row.2,
+            secret: row.3,
+            active: row.4,
+            created: row.5,
+            events: Vec::new()
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Queryable)]
+pub struct Delivery {
+    #[serde(with = "models::id_serde")]
+    pub id: i64,
+    #[serde(with = "models::id_serde")]
+    pub webhook: i64,
+    pub event: WebhookEvent,
+    /// The exact body that was signed and sent.
+    pub payload: String,
+    pub created: chrono::NaiveDateTime,
+    pub attempts: i32,
+    /// When the next attempt will be made, if the delivery is still pending.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_attempt: Option<chrono::NaiveDateTime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delivered: Option<chrono::NaiveDateTime>,
+    /// The HTTP status of the last attempt.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>
+}
+
+impl Keyed for Delivery {
+    type Key = i64;
+
+    fn key(&self) -> i64 {
+        self.id
+    }
+}
+
+/// The hex encoded HMAC-SHA256 of a payload, sent as `X-Valyou-Signature: sha256=<signature>`.
+pub fn sign(secret: &str, payload: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_varkey(secret.as_bytes()).expect("hmac accepts keys of any length");
+    mac.input(payload.as_bytes());
+
+    hex::encode(mac.result().code())
+}
+
+/// A webhook url with its host swapped for an address that was checked, along with the `Host` header
+/// naming the original host.
+pub struct Checked {
+    pub url: Url,
+    pub host: Option<String>
+}
+
+/// Checks that a webhook's host only resolves to public addresses, so that webhooks can't be used to reach
+/// the server's own network. This is done when the webhook is saved and again before every delivery, since
+/// what a host resolves to can change in between, and deliveries connect to the checked address so it can't
+/// change again before sending. Setting `WEBHOOK_ALLOW_PRIVATE=true` turns the check off for trying webhooks
+/// out locally.
+pub fn check_url(url: &str) -> Result<Checked, String> {
+    let mut parsed = Url::parse(url).map_err(|_| "invalid url".to_string())?;
+
+    if dotenv::var("WEBHOOK_ALLOW_PRIVATE").map_or(false, |allow| allow == "true") {
+        return Ok(Checked { url: parsed, host: None });
+    }
+
+    let addresses: Vec<IpAddr> = match parsed.host() {
+        Some(Host::Ipv4(ip)) => vec![IpAddr::V4(ip)],
+        Some(Host::Ipv6(ip)) => vec![IpAddr::V6(ip)],
+        Some(Host::Domain(domain)) => {
+            let port = parsed.port_or_known_default().unwrap_or(80);
+
+            (domain, port).to_socket_addrs()
+                .map_err(|_| format!("could not resolve {}", domain))?
+                .map(|address| address.ip())
+                .collect()
+        },
+        None => return Err("url has no host".into())
+    };
+
+    let address = match addresses.first() {
+        Some(&address) if addresses.iter().all(|&ip| is_public(ip)) => address,
+        _ => return Err("webhooks can only be sent to public addresses".into())
+    };
+
+    let host = match parsed.port() {
+        Some(port) => parsed.host_str().map(|host| format!("{}:{}", host, port)),
+        None => parsed.host_str().map(String::from)
+    };
+
+    parsed.set_ip_host(address).map_err(|_| "invalid url".to_string())?;
+
+    Ok(Checked { url: parsed, host })
+}
+
+/// Whether an address is outside the loopback, private, link-local, unique-local, multicast and reserved ranges.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let octets = ip.octets();
+
+            !(ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified() || ip.is_multicast()
+                // this network, carrier-grade nat, and the reserved range that also holds the broadcast address
+                || octets[0] == 0 || (octets[0] == 100 && octets[1] & 0xc0 == 64) || octets[0] >= 240)
+        },
+        IpAddr::V6(ip) => {
+            let segments = ip.segments();
+
+            if ip.is_loopback() || ip.is_unspecified() || ip.is_multicast()
+                || segments[0] & 0xfe00 == 0xfc00 || segments[0] & 0xffc0 == 0xfe80 {
+                false
+            } else if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+                // nat64 addresses reach the ipv4 address in their last 32 bits
+                is_public(IpAddr::V4(embedded_ipv4(segments[6], segments[7])))
+            } else if segments[0] == 0x2002 {
+                // 6to4 addresses reach the ipv4 address right after their prefix
+                is_public(IpAddr::V4(embedded_ipv4(segments[1], segments[2])))
+            } else {
+                // ipv4 mapped and compatible addresses are checked as the ipv4 address they stand for
+                ip.to_ipv4().map_or(true, |ip| is_public(IpAddr::V4(ip)))
+            }
+        }
+    }
+}
+
+fn embedded_ipv4(high: u16, low: u16) -> Ipv4Addr {
+    Ipv4Addr::from((u32::from(high) << 16) | u32::from(low))
+}
+
+/// How many seconds to wait before retrying a delivery that has failed this many times.
+pub fn backoff(attempts: i32) -> i64 {
+    RETRY_DELAY << (attempts - 1).max(0).min(16)
+}
+
+impl ToSql<db::WebhookEvent, Pg> for WebhookEvent {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, Pg>) -> serialize::Result {
+        out.write_all(self.name().as_bytes())?;
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<db::WebhookEvent, Pg> for WebhookEvent {
+    fn from_sql(bytes: Option<&[u8]>) -> deserialize::Result<Self> {
+        match not_none!(bytes) {
+            b"entry_created" => Ok(WebhookEvent::EntryCreated),
+            b"friend_request" => Ok(WebhookEvent::FriendRequest),
+            b"journal_updated" => Ok(WebhookEvent::JournalUpdated),
+            b"ping" => Ok(WebhookEvent::Ping),
+            _ => Err("Unrecognized enum variant".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn public(ip: &str) -> bool {
+        is_public(ip.parse().unwrap())
+    }
+
+    #[test]
+    fn public_addresses_are_allowed() {
+        assert!(public("93.184.216.34"));
+        assert!(public("1.1.1.1"));
+        assert!(public("2606:2800:220:1:248:1893:25c8:1946"));
+    }
+
+    #[test]
+    fn private_addresses_are_rejected() {
+        for ip in &["127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "0.0.0.0", "0.1.2.3"] {
+            assert!(!public(ip), "{}", ip);
+        }
+
+        for ip in &["::1", "::", "fc00::1", "fd12:3456::1", "fe80::1"] {
+            assert!(!public(ip), "{}", ip);
+        }
+    }
+
+    #[test]
+    fn carrier_grade_nat_is_rejected() {
+        assert!(!public("100.64.0.1"));
+        assert!(!public("100.127.255.254"));
+        assert!(public("100.63.255.255"));
+        assert!(public("100.128.0.1"));
+    }
+
+    #[test]
+    fn multicast_and_reserved_are_rejected() {
+        for ip in &["224.0.0.1", "239.255.255.250", "240.0.0.1", "255.255.255.255", "ff02::1"] {
+            assert!(!public(ip), "{}", ip);
+        }
+    }
+
+    #[test]
+    fn mapped_addresses_are_checked_as_ipv4() {
+        assert!(!public("::ffff:127.0.0.1"));
+        assert!(!public("::ffff:10.0.0.1"));
+        assert!(!public("::ffff:100.64.0.1"));
+        assert!(public("::ffff:93.184.216.34"));
+    }
+
+    #[test]
+    fn nat64_and_6to4_are_checked_as_ipv4() {
+        assert!(!public("64:ff9b::7f00:1"));
+        assert!(!public("64:ff9b::a9fe:a9fe"));
+        assert!(public("64:ff9b::5db8:d822"));
+
+        assert!(!public("2002:c0a8:101::1"));
+        assert!(!public("2002:7f00:1::"));
+        assert!(public("2002:5db8:d822::1"));
+    }
+}
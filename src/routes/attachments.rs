@@ -0,0 +1,182 @@
+use actix_identity::Identity;
+use actix_web::{HttpMessage, HttpRequest, HttpResponse, web};
+use diesel::prelude::*;
+
+use crate::errors::{Error, RequestResult, ValyouResult};
+use crate::models::attachments::{Attachment, clean_filename, FILE_TYPES, MAX_ATTACHMENT_SIZE, THUMBNAIL_SIZE};
+use crate::Pool;
+use crate::routes::account::get_identity;
+use crate::routes::entries::{check_author, check_visible};
+use crate::schema::attachments;
+use crate::storage::{images, read, Storage};
+
+#[derive(Debug, Deserialize)]
+pub struct UploadQuery {
+    pub filename: Option<String>
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "attachments"]
+pub struct NewAttachment {
+    pub entry: i64,
+    pub filename: String,
+    pub mime: String,
+    pub size: i32,
+    pub thumbnail: bool
+}
+
+/// Attaches the request body to an entry, using the request's content type as the file's type.
+pub async fn upload(path: web::Path<(i64, i64)>, query: web::Query<UploadQuery>, body: web::Bytes, req: HttpRequest, ident: Identity, pool: web::Data<Pool>, storage: web::Data<Storage>) -> RequestResult {
+    let (jid, eid) = path.into_inner();
+    let me = get_identity(&ident)?.userid;
+
+    if body.is_empty() {
+        return Err(Error::BadRequest("attachment is empty".into()));
+    }
+
+    if body.len() > MAX_ATTACHMENT_SIZE {
+        return Err(Error::BadRequest(format!("attachments are limited to {} bytes", MAX_ATTACHMENT_SIZE)));
+    }
+
+    let mime = req.content_type().to_ascii_lowercase();
+    let format = images::format_for(&mime);
+
+    if format.is_none() && !FILE_TYPES.contains(&mime.as_str()) {
+        return Err(Error::BadRequest(format!("cannot attach files of type {}", mime)));
+    }
+
+    let db = pool.get()?;
+
+    // only the author gets to have an upload decoded
+    check_author(jid, eid, me, &db)?;
+
+    let thumbnail = match format {
+        Some(format) => {
+            let data = body.clone();
+            Some(web::block(move || images::thumbnail(&images::load(&data, format)?, THUMBNAIL_SIZE)).await?)
+        },
+        None => None
+    };
+
+    let new_attachment = NewAttachment {
+        entry: eid,
+        filename: clean_filename(query.filename.as_deref().unwrap_or("")),
+        mime,
+        size: body.len() as i32,
+        thumbnail: thumbnail.is_some()
+    };
+
+    let attachment: Attachment = {
+        use self::attachments::dsl::*;
+
+        diesel::insert_into(attachments)
+            .values(&new_attachment)
+            .get_result(&db)?
+    };
+
+    let (key, thumbnail_key) = (attachment.key(), attachment.thumbnail_key());
+    let storage = storage.get_ref().clone();
+
+    let stored = web::block(move || {
+        storage.put(&key, &body)?;
+
+        match &thumbnail {
+            Some(thumb) => storage.put(&thumbnail_key, thumb),
+            None => Ok(())
+        }
+    }).await;
+
+    // deleting the row queues whatever was stored for removal
+    if let Err(e) = stored {
+        use self::attachments::dsl::*;
+
+        diesel::delete(attachments)
+            .filter(attachmentid.eq(attachment.id))
+            .execute(&db)?;
+
+        return Err(e.into());
+    }
+
+    Ok(HttpResponse::Created().json(attachment))
+}
+
+pub async fn list(path: web::Path<(i64, i64)>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let (jid, eid) = path.into_inner();
+    let me = get_identity(&ident)?.userid;
+
+    let db = pool.get()?;
+
+    check_visible(jid, eid, me, &db)?;
+
+    use self::attachments::dsl::*;
+    let found: Vec<Attachment> = attachments
+        .filter(entry.eq(eid))
+        .order(attachmentid.asc())
+        .get_results(&db)?;
+
+    Ok(HttpResponse::Ok().json(found))
+}
+
+pub async fn download(path: web::Path<(i64, i64, i64)>, ident: Identity, pool: web::Data<Pool>, storage: web::Data<Storage>) -> RequestResult {
+    let (jid, eid, aid) = path.into_inner();
+    let me = get_identity(&ident)?.userid;
+
+    let attachment = find_visible(jid, eid, aid, me, &pool)?;
+    let data = read(&storage, attachment.key()).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(attachment.mime.as_str())
+        .header("Content-Disposition", format!("inline; filename=\"{}\"", attachment.filename))
+        .body(data))
+}
+
+pub async fn thumbnail(path: web::Path<(i64, i64, i64)>, ident: Identity, pool: web::Data<Pool>, storage: web::Data<Storage>) -> RequestResult {
+    let (jid, eid, aid) = path.into_inner();
+    let me = get_identity(&ident)?.userid;
+
+    let attachment = find_visible(jid, eid, aid, me, &pool)?;
+
+    if !attachment.thumbnail {
+        return Err(Error::NotFound);
+    }
+
+    let data = read(&storage, attachment.thumbnail_key()).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("image/png")
+        .body(data))
+}
+
+/// Deletes an attachment, leaving its files to the cleanup job.
+pub async fn delete(path: web::Path<(i64, i64, i64)>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let (jid, eid, aid) = path.into_inner();
+    let me = get_identity(&ident)?.userid;
+
+    let db = pool.get()?;
+
+    check_author(jid, eid, me, &db)?;
+
+    use self::attachments::dsl::*;
+    let deleted = diesel::delete(attachments)
+        .filter(attachmentid.eq(aid).and(entry.eq(eid)))
+        .execute(&db)?;
+
+    if deleted == 0 {
+        return Err(Error::NotFound);
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+fn find_visible(jid: i64, eid: i64, aid: i64, me: i64, pool: &web::Data<Pool>) -> ValyouResult<Attachment> {
+    let db = pool.get()?;
+
+    check_visible(jid, eid, me, &db)?;
+
+    use self::attachments::dsl::*;
+    let found: Attachment = attachments
+        .filter(attachmentid.eq(aid).and(entry.eq(eid)))
+        .get_result(&db)?;
+
+    Ok(found)
+}
@@ -0,0 +1,196 @@
+use std::collections::BTreeMap;
+
+use actix_identity::Identity;
+use actix_web::{HttpRequest, HttpResponse, web};
+use chrono::NaiveDate;
+use diesel::prelude::*;
+
+use crate::errors::{Error, RequestResult, ValyouResult};
+use crate::models::{can_see, created_between, local_date};
+use crate::models::entries::{Calendar, Day, Entry};
+use crate::models::search::{Page, Paginated, SearchMethod, SearchQuery};
+use crate::Pool;
+use crate::routes::account::get_identity;
+use crate::routes::entries::decorate;
+use crate::routes::profiles::get_timezone;
+
+/// A single day, or every day from one to another, in the user's timezone.
+#[derive(Debug, Deserialize)]
+pub struct DateRange {
+    pub from: NaiveDate,
+    /// The last day, which defaults to `from`.
+    pub until: Option<NaiveDate>
+}
+
+impl DateRange {
+    fn days(&self) -> ValyouResult<(NaiveDate, NaiveDate)> {
+        let until = self.until.unwrap_or(self.from);
+
+        if until < self.from {
+            Err(Error::BadRequest("date range ends before it starts".into()))
+        } else {
+            Ok((self.from, until))
+        }
+    }
+}
+
+/// Counts the user's own entries on each day of a month.
+pub async fn own_month(path: web::Path<(i32, u32)>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    let (year, month) = path.into_inner();
+    let (first, last) = month_bounds(year, month)?;
+
+    let db = pool.get()?;
+    let tz = get_timezone(me, &db)?;
+
+    let dates: Vec<NaiveDate> = {
+        use crate::views::visible_entries::dsl::*;
+
+        visible_entries
+            .select(local_date(created, tz.as_str()))
+            .filter(author.eq(me).and(created_between(created, tz.as_str(), first, last)))
+            .get_results(&db)?
+    };
+
+    Ok(HttpResponse::Ok().json(calendar(year, month, dates)))
+}
+
+/// Counts the entries the user can see in a journal on each day of a month.
+pub async fn journal_month(path: web::Path<(i64, i32, u32)>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    let (jid, year, month) = path.into_inner();
+    let (first, last) = month_bounds(year, month)?;
+
+    let db = pool.get()?;
+    let tz = get_timezone(me, &db)?;
+
+    let dates: Vec<NaiveDate> = {
+        use crate::views::visible_entries::dsl::*;
+
+        visible_entries
+            .select(local_date(created, tz.as_str()))
+            .filter(journal.eq(jid).and(can_see(me, author, journal)))
+            .filter(published.or(author.eq(me)))
+            .filter(created_between(created, tz.as_str(), first, last))
+            .get_results(&db)?
+    };
+
+    Ok(HttpResponse::Ok().json(calendar(year, month, dates)))
+}
+
+/// Lists the entries the user can see from a range of days across every journal.
+pub async fn days(path: web::Path<SearchMethod>, query: web::Query<SearchQuery>, range: web::Query<DateRange>, req: HttpRequest, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    between(me, None, path.into_inner(), query.into_inner(), &range, &req, &pool.get()?)
+}
+
+/// Lists the entries the user can see from a range of days in a journal.
+pub async fn journal_days(path: web::Path<(i64, SearchMethod)>, query: web::Query<SearchQuery>, range: web::Query<DateRange>, req: HttpRequest, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    let (jid, method) = path.into_inner();
+
+    between(me, Some(jid), method, query.into_inner(), &range, &req, &pool.get()?)
+}
+
+fn between(me: i64, jid: Option<i64>, method: SearchMethod, query: SearchQuery, range: &DateRange, req: &HttpRequest, db: &PgConnection) -> RequestResult {
+    let (first, last) = range.days()?;
+    let Page { method, key, limit } = query.page::<i64>(method)?;
+
+    let tz = get_timezone(me, db)?;
+
+    let mut found: Vec<Entry> = {
+        use crate::views::visible_entries::dsl::*;
+
+        let mut filtered = visible_entries
+            .filter(created_between(created, tz.as_str(), first, last))
+            .filter(can_see(me, author, journal).and(published.or(author.eq(me))))
+            .into_boxed();
+
+        if let Some(jid) = jid {
+            filtered = filtered.filter(journal.eq(jid));
+        }
+
+        match method {
+            SearchMethod::Before => {
+                filtered
+                    .filter(entryid.lt(key))
+                    .order(entryid.desc())
+                    .limit(limit)
+                    .get_results(db)?
+            },
+            SearchMethod::After => {
+                filtered
+                    .filter(entryid.gt(key))
+                    .order(entryid.asc())
+                    .limit(limit)
+                    .get_results(db)?
+            }
+        }
+    };
+
+    decorate(&mut found, me, db)?;
+
+    Ok(HttpResponse::Ok().json(Paginated::paginate(found, method, limit, req)))
+}
+
+/// The first and last day of a month.
+fn month_bounds(year: i32, month: u32) -> ValyouResult<(NaiveDate, NaiveDate)> {
+    let invalid = || Error::BadRequest("invalid month".into());
+
+    let first = NaiveDate::from_ymd_opt(year, month, 1).ok_or_else(invalid)?;
+    let next = match month {
+        12 => NaiveDate::from_ymd_opt(year + 1, 1, 1),
+        _ => NaiveDate::from_ymd_opt(year, month + 1, 1)
+    };
+
+    Ok((first, next.ok_or_else(invalid)?.pred()))
+}
+
+fn calendar(year: i32, month: u32, dates: Vec<NaiveDate>) -> Calendar {
+    let mut counts: BTreeMap<NaiveDate, i64> = BTreeMap::new();
+
+    for date in dates {
+        *counts.entry(date).or_default() += 1;
+    }
+
+    Calendar {
+        year,
+        month,
+        days: counts.into_iter().map(|(date, entries)| Day { date, entries }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd(year, month, day)
+    }
+
+    #[test]
+    fn month_bounds_cover_the_whole_month() {
+        assert_eq!(month_bounds(2020, 1).unwrap(), (date(2020, 1, 1), date(2020, 1, 31)));
+        assert_eq!(month_bounds(2020, 4).unwrap(), (date(2020, 4, 1), date(2020, 4, 30)));
+        assert_eq!(month_bounds(2019, 12).unwrap(), (date(2019, 12, 1), date(2019, 12, 31)));
+    }
+
+    #[test]
+    fn month_bounds_know_leap_years() {
+        assert_eq!(month_bounds(2020, 2).unwrap().1, date(2020, 2, 29));
+        assert_eq!(month_bounds(2019, 2).unwrap().1, date(2019, 2, 28));
+        assert_eq!(month_bounds(1900, 2).unwrap().1, date(1900, 2, 28));
+        assert_eq!(month_bounds(2000, 2).unwrap().1, date(2000, 2, 29));
+    }
+
+    #[test]
+    fn month_bounds_reject_invalid_months() {
+        assert!(month_bounds(2020, 0).is_err());
+        assert!(month_bounds(2020, 13).is_err());
+        assert!(month_bounds(std::i32::MAX, 12).is_err());
+    }
+}
@@ -0,0 +1,160 @@
+use actix_identity::Identity;
+use actix_web::{HttpRequest, HttpResponse, web};
+use diesel::prelude::*;
+
+use crate::errors::{Error, RequestResult};
+use crate::events::Events;
+use crate::models;
+use crate::models::comments::Comment;
+use crate::models::notifications::NotificationKind;
+use crate::models::search::{Page, Paginated, SearchMethod, SearchQuery};
+use crate::Pool;
+use crate::routes::account::get_identity;
+use crate::routes::entries::{check_author, check_visible};
+use crate::routes::notifications::notify;
+use crate::schema::{comments, entries};
+
+#[derive(Debug, Deserialize)]
+pub struct CreateRequest {
+    pub content: String,
+    #[serde(default, with = "models::option_id_serde")]
+    pub parent: Option<i64>
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "comments"]
+pub struct NewComment {
+    pub entry: i64,
+    pub author: i64,
+    pub parent: Option<i64>,
+    pub content: String
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EditRequest {
+    pub content: String
+}
+
+pub async fn list(path: web::Path<(i64, i64, SearchMethod)>, query: web::Query<SearchQuery>, req: HttpRequest, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    let (jid, eid, method) = path.into_inner();
+    let Page { method, key, limit } = query.into_inner().page::<i64>(method)?;
+
+    let db = pool.get()?;
+
+    check_visible(jid, eid, me, &db)?;
+
+    let found: Vec<Comment> = {
+        use self::comments::dsl::*;
+
+        match method {
+            SearchMethod::Before => {
+                comments
+                    .filter(commentid.lt(key).and(entry.eq(eid)))
+                    .order(commentid.desc())
+                    .limit(limit)
+                    .get_results(&db)?
+            },
+            SearchMethod::After => {
+                comments
+                    .filter(commentid.gt(key).and(entry.eq(eid)))
+                    .order(commentid.asc())
+                    .limit(limit)
+                    .get_results(&db)?
+            }
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(Paginated::paginate(found, method, limit, &req)))
+}
+
+pub async fn create(path: web::Path<(i64, i64)>, json: web::Json<CreateRequest>, ident: Identity, pool: web::Data<Pool>, events: web::Data<Events>) -> RequestResult {
+    let (jid, eid) = path.into_inner();
+    let me = get_identity(&ident)?.userid;
+    let CreateRequest { content, parent } = json.into_inner();
+
+    let db = pool.get()?;
+
+    let entry_author = check_visible(jid, eid, me, &db)?;
+
+    let new_comment = NewComment {
+        entry: eid,
+        author: me,
+        parent,
+        content
+    };
+
+    use self::comments::dsl::*;
+    let comment: Comment = diesel::insert_into(comments)
+        .values(&new_comment)
+        .get_result(&db)?;
+
+    // the author of the entry only hears about replies to their own comments once
+    let replied_to = match comment.parent {
+        Some(pid) => {
+            let parent_author: i64 = comments
+                .select(author)
+                .filter(commentid.eq(pid))
+                .get_result(&db)?;
+
+            notify(parent_author, NotificationKind::Reply, me, Some(eid), Some(comment.id), &events, &db)?;
+            Some(parent_author)
+        },
+        None => None
+    };
+
+    if replied_to != Some(entry_author) {
+        notify(entry_author, NotificationKind::Comment, me, Some(eid), Some(comment.id), &events, &db)?;
+    }
+
+    Ok(HttpResponse::Created().json(comment))
+}
+
+/// Comments can only be edited by the people who wrote them.
+pub async fn edit(path: web::Path<(i64, i64, i64)>, json: web::Json<EditRequest>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let (jid, eid, cid) = path.into_inner();
+    let me = get_identity(&ident)?.userid;
+
+    let db = pool.get()?;
+
+    check_visible(jid, eid, me, &db)?;
+
+    use self::comments::dsl::*;
+    let comment: Comment = diesel::update(comments)
+        .filter(commentid.eq(cid).and(entry.eq(eid)).and(author.eq(me)))
+        .set(content.eq(json.into_inner().content))
+        .get_result(&db)?;
+
+    Ok(HttpResponse::Ok().json(comment))
+}
+
+/// Comments can be deleted by the people who wrote them, or by the author of the entry.
+/// Deleting a top level comment also deletes its replies.
+pub async fn delete(path: web::Path<(i64, i64, i64)>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let (jid, eid, cid) = path.into_inner();
+    let me = get_identity(&ident)?.userid;
+
+    let db = pool.get()?;
+
+    use self::comments::dsl::*;
+
+    let in_journal = entries::table
+        .select(entries::entryid)
+        .filter(entries::journal.eq(jid));
+    let target = comments.filter(commentid.eq(cid).and(entry.eq(eid)).and(entry.eq_any(in_journal)));
+
+    // authors can always take back their own comments, even from entries they can no longer see
+    let mut deleted = diesel::delete(target.clone().filter(author.eq(me))).execute(&db)?;
+
+    if deleted == 0 {
+        check_author(jid, eid, me, &db)?;
+        deleted = diesel::delete(target).execute(&db)?;
+    }
+
+    if deleted == 0 {
+        return Err(Error::NotFound);
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,55 @@
+use actix_identity::Identity;
+use actix_web::{HttpResponse, web};
+use diesel::prelude::*;
+
+use crate::errors::{Error, RequestResult};
+use crate::models::digests::{DigestFrequency, Subscription};
+use crate::Pool;
+use crate::routes::account::get_identity;
+
+#[derive(Debug, Deserialize)]
+pub struct SubscribeRequest {
+    pub frequency: DigestFrequency
+}
+
+pub async fn view(ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    use crate::schema::digest_subscriptions::dsl::*;
+    let found: Subscription = digest_subscriptions
+        .filter(userid.eq(me))
+        .get_result(&pool.get()?)?;
+
+    Ok(HttpResponse::Ok().json(found))
+}
+
+/// Subscribes to digests, or changes how often they arrive without restarting the current period.
+pub async fn subscribe(json: web::Json<SubscribeRequest>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+    let chosen = json.into_inner().frequency;
+
+    use crate::schema::digest_subscriptions::dsl::*;
+    let found: Subscription = diesel::insert_into(digest_subscriptions)
+        .values(&(userid.eq(me), frequency.eq(chosen)))
+        .on_conflict(userid)
+        .do_update()
+        .set(frequency.eq(chosen))
+        .get_result(&pool.get()?)?;
+
+    Ok(HttpResponse::Ok().json(found))
+}
+
+pub async fn unsubscribe(ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    use crate::schema::digest_subscriptions::dsl::*;
+    let deleted = diesel::delete(digest_subscriptions)
+        .filter(userid.eq(me))
+        .execute(&pool.get()?)?;
+
+    if deleted == 0 {
+        return Err(Error::NotFound);
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
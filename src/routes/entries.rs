@@ -1,14 +1,20 @@
 use std::cmp::min;
+use std::slice;
 
 use actix_identity::Identity;
-use actix_web::{HttpResponse, web};
+use actix_web::{HttpRequest, HttpResponse, web};
 use diesel::{prelude::*, QueryDsl};
 
-use crate::errors::RequestResult;
-use crate::models::{can_see, entries::Entry};
-use crate::models::search::{Paginated, SearchMethod, SearchQuery};
+use crate::errors::{ConstraintViolation, Error, RequestResult, ValyouResult};
+use crate::events::Events;
+use crate::markdown;
+use crate::models::can_see;
+use crate::models::entries::{check_content, Entry};
+use crate::models::search::{Page, Paginated, SearchMethod, SearchQuery};
 use crate::Pool;
 use crate::routes::account::get_identity;
+use crate::routes::feed::EntryFilter;
+use crate::routes::{events as feed_events, mentions, reactions};
 use crate::schema::entries;
 
 #[derive(Debug, Deserialize)]
@@ -16,6 +22,12 @@ pub struct CreateRequest {
     pub content: String,
     pub significance: Option<f64>,
     pub tags: Vec<String>,
+    #[serde(default)]
+    pub private_revisions: bool,
+    /// Drafts are only visible to their author until they are published, and can't also be scheduled.
+    #[serde(default)]
+    pub draft: bool,
+    pub publish_at: Option<chrono::NaiveDateTime>,
 }
 
 #[derive(Debug, Deserialize, AsChangeset)]
@@ -23,6 +35,10 @@ pub struct CreateRequest {
 pub struct EditRequest {
     pub content: Option<String>,
     pub significance: Option<f64>,
+    pub private_revisions: Option<bool>,
+    pub publish_at: Option<chrono::NaiveDateTime>,
+    #[serde(skip_deserializing)]
+    pub html: Option<String>,
 }
 
 #[derive(Debug, Insertable)]
@@ -31,26 +47,43 @@ pub struct NewEntry {
     pub author: i64,
     pub journal: i64,
     pub content: String,
-    pub significance: Option<f64>
+    pub significance: Option<f64>,
+    pub private_revisions: bool,
+    pub published: bool,
+    pub publish_at: Option<chrono::NaiveDateTime>,
+    pub html: Option<String>
 }
 
-pub async fn create(path: web::Path<i64>, form: web::Json<CreateRequest>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+pub async fn create(path: web::Path<i64>, form: web::Json<CreateRequest>, ident: Identity, pool: web::Data<Pool>, events: web::Data<Events>) -> RequestResult {
     let CreateRequest {
         content,
         significance,
-        mut tags
+        mut tags,
+        private_revisions,
+        draft,
+        publish_at
     } = form.into_inner();
     let jid = path.into_inner();
 
     let claims = get_identity(&ident)?;
 
+    check_content(&content)?;
+
+    if draft && publish_at.is_some() {
+        return Err(Error::BadRequest("drafts can't be scheduled, leave out draft to schedule the entry".into()));
+    }
+
     let db = pool.get()?;
 
     let new_entry = NewEntry {
         author: claims.userid,
         journal: jid,
+        html: Some(markdown::render(&content)),
         content,
-        significance
+        significance,
+        private_revisions,
+        published: !draft && publish_at.is_none(),
+        publish_at
     };
 
     let new: i64 = {
@@ -61,6 +94,12 @@ pub async fn create(path: web::Path<i64>, form: web::Json<CreateRequest>, ident:
             .get_result(&db)?
     };
 
+    // mentioned users are only told about drafts and scheduled entries once they can read them
+    if new_entry.published {
+        mentions::save(new, &new_entry.content, &events, &db)?;
+        feed_events::entry_published(new, new_entry.author, jid, &events, &db)?;
+    }
+
     if !tags.is_empty() {
         use crate::schema::entry_tags::dsl::*;
 
@@ -80,41 +119,67 @@ pub async fn create(path: web::Path<i64>, form: web::Json<CreateRequest>, ident:
 
 }
 
-pub async fn edit(path: web::Path<(i64, i64)>, json: web::Json<EditRequest>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+pub async fn edit(path: web::Path<(i64, i64)>, json: web::Json<EditRequest>, ident: Identity, pool: web::Data<Pool>, events: web::Data<Events>) -> RequestResult {
     let (jid, eid) = path.into_inner();
     let me = get_identity(&ident)?.userid;
 
-    use crate::schema::entries::dsl::*;
+    let mut changes = json.into_inner();
 
-    let entry: Entry = diesel::update(entries)
-        .filter(entryid.eq(eid).and(journal.eq(jid)).and(author.eq(me)))
-        .set(json.into_inner())
-        .returning((entryid, author, journal, created, modified, modifiedc, content, significance))
-        .get_result(&pool.get()?)?;
+    if let Some(new_content) = &changes.content {
+        check_content(new_content)?;
+        changes.html = Some(markdown::render(new_content));
+    }
+
+    let db = pool.get()?;
+
+    let existing = own_entry(jid, eid, me, &db)?;
+
+    let new_content = changes.content.clone().filter(|c| *c != existing.content);
 
-    Ok(HttpResponse::Ok().json(entry))
+    if new_content.is_some() && !existing.can_edit_content() {
+        return Err(ConstraintViolation::EditWindow.into());
+    }
+
+    {
+        use crate::schema::entries::dsl::*;
+
+        diesel::update(entries)
+            .filter(entryid.eq(eid).and(journal.eq(jid)).and(author.eq(me)))
+            .set(changes)
+            .execute(&db)?;
+    }
+
+    if let Some(new_content) = new_content.filter(|_| existing.published) {
+        mentions::save(eid, &new_content, &events, &db)?;
+    }
+
+    Ok(HttpResponse::Ok().json(own_entry(jid, eid, me, &db)?))
 }
 
-pub async fn in_journal(path: web::Path<(i64, SearchMethod)>, query: web::Query<SearchQuery>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+pub async fn in_journal(path: web::Path<(i64, SearchMethod)>, query: web::Query<SearchQuery>, filter: web::Query<EntryFilter>, req: HttpRequest, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
     let me = get_identity(&ident)?.userid;
 
     let (journalid, method) = path.into_inner();
-    let (id, limit) = query.into_inner().into_parts();
+    let Page { method, key, limit } = query.into_inner().page::<i64>(method)?;
 
-    let found: Vec<Entry> = {
+    let mut found: Vec<Entry> = {
         use crate::views::visible_entries::dsl::*;
 
+        let filtered = filter.into_inner().apply(visible_entries.into_boxed())?;
+
         match method {
             SearchMethod::Before => {
-                visible_entries
-                    .filter(entryid.lt(id).and(journal.eq(journalid)).and(can_see(me, author, journal)))
+                filtered
+                    .filter(entryid.lt(key).and(journal.eq(journalid)).and(can_see(me, author, journal)))
+                    .filter(published.or(author.eq(me)))
                     .order(entryid.desc())
                     .limit(limit)
                     .get_results(&pool.get()?)?
             },
             SearchMethod::After => {
-                visible_entries
-                    .filter(entryid.gt(id).and(journal.eq(journalid)).and(can_see(me, author, journal)))
+                filtered
+                    .filter(entryid.gt(key).and(journal.eq(journalid)).and(can_see(me, author, journal)))
+                    .filter(published.or(author.eq(me)))
                     .order(entryid.asc())
                     .limit(limit)
                     .get_results(&pool.get()?)?
@@ -122,7 +187,9 @@ pub async fn in_journal(path: web::Path<(i64, SearchMethod)>, query: web::Query<
         }
     };
 
-    Ok(HttpResponse::Ok().json(Paginated::paginate(found, method)))
+    decorate(&mut found, me, &pool.get()?)?;
+
+    Ok(HttpResponse::Ok().json(Paginated::paginate(found, method, limit, &req)))
 }
 
 pub async fn find(path: web::Path<(i64, i64)>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
@@ -130,13 +197,195 @@ pub async fn find(path: web::Path<(i64, i64)>, ident: Identity, pool: web::Data<
 
     let me = get_identity(&ident)?.userid;
 
-    let found: Entry = {
+    let mut found: Entry = {
         use crate::views::visible_entries::dsl::*;
 
         visible_entries
             .filter(entryid.eq(eid).and(journal.eq(jid)).and(can_see(me, author, journal)))
+            .filter(published.or(author.eq(me)))
             .get_result(&pool.get()?)?
     };
 
+    decorate(slice::from_mut(&mut found), me, &pool.get()?)?;
+
     Ok(HttpResponse::Ok().json(found))
 }
+
+pub async fn hide(path: web::Path<(i64, i64)>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let (jid, eid) = path.into_inner();
+    let me = get_identity(&ident)?.userid;
+
+    set_hidden(jid, eid, me, true, &pool)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn unhide(path: web::Path<(i64, i64)>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let (jid, eid) = path.into_inner();
+    let me = get_identity(&ident)?.userid;
+
+    set_hidden(jid, eid, me, false, &pool)?;
+
+    find(web::Path::from((jid, eid)), ident, pool).await
+}
+
+pub async fn delete(path: web::Path<(i64, i64)>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let (jid, eid) = path.into_inner();
+    let me = get_identity(&ident)?.userid;
+
+    let db = pool.get()?;
+
+    check_author(jid, eid, me, &db)?;
+
+    // the files of its attachments are queued for removal as they cascade
+    {
+        use crate::schema::entries::dsl::*;
+
+        diesel::delete(entries)
+            .filter(entryid.eq(eid))
+            .execute(&db)?;
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn publish(path: web::Path<(i64, i64)>, ident: Identity, pool: web::Data<Pool>, events: web::Data<Events>) -> RequestResult {
+    let (jid, eid) = path.into_inner();
+    let me = get_identity(&ident)?.userid;
+
+    let db = pool.get()?;
+
+    // publishing gives the entry a new id, which is the one returned
+    let published_entry: Option<(i64, String)> = {
+        use crate::schema::entries::dsl::*;
+
+        diesel::update(entries)
+            .filter(entryid.eq(eid).and(journal.eq(jid)).and(author.eq(me)).and(published.eq(false)))
+            .set(published.eq(true))
+            .returning((entryid, content))
+            .get_result(&db)
+            .optional()?
+    };
+
+    let eid = match published_entry {
+        Some((new, body)) => {
+            mentions::save(new, &body, &events, &db)?;
+            feed_events::entry_published(new, me, jid, &events, &db)?;
+            new
+        },
+        None => {
+            check_author(jid, eid, me, &db)?;
+            eid
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(own_entry(jid, eid, me, &db)?))
+}
+
+/// Turns an entry back into a draft, cancelling any scheduled publishing.
+pub async fn unpublish(path: web::Path<(i64, i64)>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let (jid, eid) = path.into_inner();
+    let me = get_identity(&ident)?.userid;
+
+    let db = pool.get()?;
+
+    {
+        use crate::schema::entries::dsl::*;
+        let updated = diesel::update(entries)
+            .filter(entryid.eq(eid).and(journal.eq(jid)).and(author.eq(me)))
+            .set((published.eq(false), publish_at.eq(None::<chrono::NaiveDateTime>)))
+            .execute(&db)?;
+
+        if updated == 0 {
+            return Err(Error::NotFound);
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(own_entry(jid, eid, me, &db)?))
+}
+
+fn set_hidden(jid: i64, eid: i64, me: i64, value: bool, pool: &web::Data<Pool>) -> ValyouResult<()> {
+    use crate::schema::entries::dsl::*;
+    let updated = diesel::update(entries)
+        .filter(entryid.eq(eid).and(journal.eq(jid)).and(author.eq(me)))
+        .set(hidden.eq(value))
+        .execute(&pool.get()?)?;
+
+    if updated > 0 {
+        Ok(())
+    } else {
+        Err(Error::NotFound)
+    }
+}
+
+/// Finds one of the user's own entries, whether or not it is hidden.
+fn own_entry(jid: i64, eid: i64, me: i64, db: &PgConnection) -> ValyouResult<Entry> {
+    let visible: Option<Entry> = {
+        use crate::views::visible_entries::dsl::*;
+
+        visible_entries
+            .filter(entryid.eq(eid).and(journal.eq(jid)).and(author.eq(me)))
+            .get_result(db)
+            .optional()?
+    };
+
+    let mut found = match visible {
+        Some(found) => found,
+        None => {
+            use crate::views::hidden_entries::dsl::*;
+
+            hidden_entries
+                .filter(entryid.eq(eid).and(journal.eq(jid)).and(author.eq(me)))
+                .get_result(db)?
+        }
+    };
+
+    decorate(slice::from_mut(&mut found), me, db)?;
+
+    Ok(found)
+}
+
+/// Fills in everything about entries that depends on who is looking at them.
+pub fn decorate(found: &mut [Entry], me: i64, db: &PgConnection) -> ValyouResult<()> {
+    if found.is_empty() {
+        return Ok(());
+    }
+
+    let ids: Vec<i64> = found.iter().map(|e| e.id).collect();
+
+    let mut counts = reactions::load_counts(&ids, me, db)?;
+    let mut mentioned = mentions::load(&ids, db)?;
+
+    for entry in found.iter_mut() {
+        entry.reactions = counts.remove(&entry.id).unwrap_or_default();
+        entry.mentions = mentioned.remove(&entry.id).unwrap_or_default();
+    }
+
+    Ok(())
+}
+
+/// Checks the user can see an entry like `find` would, except authors can also see their hidden entries.
+/// Returns the entry's author.
+pub fn check_visible(jid: i64, eid: i64, me: i64, db: &PgConnection) -> ValyouResult<i64> {
+    use crate::schema::entries::dsl::*;
+
+    let found = entries
+        .select(author)
+        .filter(entryid.eq(eid).and(journal.eq(jid)).and(can_see(me, author, journal)))
+        .filter(author.eq(me).or(hidden.eq(false).and(published)))
+        .first::<i64>(db)?;
+
+    Ok(found)
+}
+
+/// Checks the user wrote an entry.
+pub fn check_author(jid: i64, eid: i64, me: i64, db: &PgConnection) -> ValyouResult<()> {
+    use crate::schema::entries::dsl::*;
+
+    entries
+        .select(entryid)
+        .filter(entryid.eq(eid).and(journal.eq(jid)).and(author.eq(me)))
+        .first::<i64>(db)?;
+
+    Ok(())
+}
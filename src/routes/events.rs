@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use actix_identity::Identity;
+use actix_web::{HttpResponse, web};
+use diesel::prelude::*;
+use futures::{future, stream, StreamExt};
+
+use crate::errors::{Error, RequestResult, ValyouResult};
+use crate::events::{Event, Events};
+use crate::models::can_see;
+use crate::models::webhooks::WebhookEvent;
+use crate::Pool;
+use crate::routes::account::get_identity;
+use crate::routes::relationships::friend_ids;
+use crate::routes::webhooks::enqueue;
+
+/// How often an idle stream sends a comment, which keeps proxies from closing it
+/// and lets the server notice clients that have gone away.
+const HEARTBEAT: Duration = Duration::from_secs(30);
+
+/// Streams the user's events as server-sent events, one JSON `Event` per message.
+pub async fn stream(ident: Identity, events: web::Data<Events>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    let updates = events.subscribe(me)
+        .filter_map(|event| future::ready(serde_json::to_string(&event).ok()))
+        .map(|json| format!("data: {}\n\n", json));
+
+    let heartbeat = stream::unfold(actix_rt::time::interval(HEARTBEAT), |mut interval| async move {
+        interval.tick().await;
+        Some((":\n\n".to_string(), interval))
+    });
+
+    let body = stream::select(updates, heartbeat)
+        .map(|message| Ok::<_, Error>(web::Bytes::from(message)));
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .header("Cache-Control", "no-cache")
+        .streaming(Box::pin(body)))
+}
+
+/// Tells the author and their friends who can see a newly published entry about it,
+/// and queues it for the author's webhooks.
+pub fn entry_published(eid: i64, author: i64, journal: i64, events: &Events, db: &PgConnection) -> ValyouResult<()> {
+    let friends: Vec<i64> = {
+        use crate::schema::profiles::dsl::*;
+
+        profiles
+            .select(userid)
+            .filter(userid.eq_any(friend_ids(author, db)?).and(can_see(userid, author, journal)))
+            .get_results(db)?
+    };
+
+    // the entry is already published, so a failed notification is only logged
+    for user in Some(author).into_iter().chain(friends) {
+        if let Err(e) = events.publish(user, Event::Entry { id: eid, journal, author }, db) {
+            log::error!("could not publish entry event: {}", e);
+        }
+    }
+
+    let data = json!({ "entry": format!("{:019}", eid), "journal": format!("{:019}", journal), "author": format!("{:019}", author) });
+    enqueue(author, WebhookEvent::EntryCreated, data, db)?;
+
+    Ok(())
+}
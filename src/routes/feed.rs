@@ -1,36 +1,141 @@
 use std::cmp::max;
 
 use actix_identity::Identity;
-use actix_web::{HttpResponse, web};
+use actix_web::{HttpRequest, HttpResponse, web};
+use chrono::Datelike;
+use diesel::dsl::{exists, now};
+use diesel::pg::Pg;
 use diesel::prelude::*;
+use diesel::sql_types::{Array, BigInt, Bool, Double, Timestamp};
 
-use crate::errors::RequestResult;
-use crate::models::can_see;
-use crate::models::entries::Entry;
-use crate::models::search::{Paginated, SearchMethod, SearchQuery};
+use crate::errors::{Error, RequestResult, ValyouResult};
+use crate::models::{self, are_friends, can_see, entry_matches, entry_rank, entry_snippet, local_date, on_this_day};
+use crate::models::entries::{Entry, Match, MAX_MEMORIES, MAX_QUERY_LENGTH, Memories, RANKED_WINDOW, Ranked, Score};
+use crate::models::search::{Page, Paginated, SearchMethod, SearchQuery};
 use crate::Pool;
 use crate::routes::account::get_identity;
+use crate::routes::entries::decorate;
+use crate::routes::profiles::get_timezone;
+use crate::views::visible_entries::{self, BoxedQuery};
 
-pub async fn timeline(path: web::Path<SearchMethod>, query: web::Query<SearchQuery>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+/// The most values any one filter accepts.
+const MAX_FILTER_VALUES: usize = 20;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedOrder {
+    Chronological,
+    Ranked
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FeedQuery {
+    #[serde(default)]
+    pub order: FeedOrder
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MemoriesQuery {
+    /// Includes the entries of friends that the user can see.
+    #[serde(default)]
+    pub friends: bool
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TextQuery {
+    pub q: String
+}
+
+/// Narrows down a list of entries. Each list matches entries with any of its values,
+/// and every filter given has to match.
+#[derive(Debug, Deserialize)]
+pub struct EntryFilter {
+    #[serde(default, deserialize_with = "models::comma_serde::deserialize")]
+    pub tags: Vec<String>,
+    #[serde(default, deserialize_with = "models::comma_serde::deserialize")]
+    pub authors: Vec<i64>,
+    #[serde(default, deserialize_with = "models::comma_serde::deserialize")]
+    pub journals: Vec<i64>,
+    /// The first day entries can have been created on.
+    pub from: Option<chrono::NaiveDate>,
+    /// The last day entries can have been created on.
+    pub until: Option<chrono::NaiveDate>,
+    pub min_significance: Option<f64>
+}
+
+impl Default for FeedOrder {
+    fn default() -> Self {
+        FeedOrder::Chronological
+    }
+}
+
+impl EntryFilter {
+    /// Adds the filters to a query. Whoever runs it is still responsible for checking `can_see`.
+    pub fn apply<'a>(self, query: BoxedQuery<'a, Pg>) -> ValyouResult<BoxedQuery<'a, Pg>> {
+        use crate::views::visible_entries::dsl::*;
+
+        if self.tags.len().max(self.authors.len()).max(self.journals.len()) > MAX_FILTER_VALUES {
+            return Err(Error::BadRequest(format!("filters are limited to {} values each", MAX_FILTER_VALUES)));
+        }
+
+        let mut query = query;
+
+        if !self.tags.is_empty() {
+            use crate::schema::entry_tags;
+
+            let tagged = entry_tags::table
+                .filter(entry_tags::tag.eq_any(self.tags))
+                .select(entry_tags::entry);
+
+            query = query.filter(entryid.eq_any(tagged));
+        }
+
+        if !self.authors.is_empty() {
+            query = query.filter(author.eq_any(self.authors));
+        }
+
+        if !self.journals.is_empty() {
+            query = query.filter(journal.eq_any(self.journals));
+        }
+
+        if let Some(day) = self.from {
+            query = query.filter(created.ge(day.and_hms(0, 0, 0)));
+        }
+
+        if let Some(day) = self.until {
+            query = query.filter(created.lt(day.and_hms(0, 0, 0) + chrono::Duration::days(1)));
+        }
+
+        if let Some(min) = self.min_significance {
+            query = query.filter(significance.ge(min));
+        }
+
+        Ok(query)
+    }
+}
+
+pub async fn timeline(path: web::Path<SearchMethod>, query: web::Query<SearchQuery>, filter: web::Query<EntryFilter>, req: HttpRequest, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
     let me = get_identity(&ident)?.userid;
 
     let method = path.into_inner();
-    let (id, limit) = query.into_inner().into_parts();
+    let Page { method, key, limit } = query.into_inner().page::<i64>(method)?;
 
-    let found: Vec<Entry> = {
+    let mut found: Vec<Entry> = {
         use crate::views::visible_entries::dsl::*;
 
+        let filtered = filter.into_inner().apply(visible_entries.into_boxed())?;
+
         match method {
             SearchMethod::Before => {
-                visible_entries
-                    .filter(entryid.lt(id).and(author.eq(me)))
+                filtered
+                    .filter(entryid.lt(key).and(author.eq(me)))
                     .order(entryid.desc())
                     .limit(limit)
                     .get_results(&pool.get()?)?
             },
             SearchMethod::After => {
-                visible_entries
-                    .filter(entryid.gt(id).and(author.eq(me)))
+                filtered
+                    .filter(entryid.gt(key).and(author.eq(me)))
                     .order(entryid.asc())
                     .limit(limit)
                     .get_results(&pool.get()?)?
@@ -38,29 +143,31 @@ pub async fn timeline(path: web::Path<SearchMethod>, query: web::Query<SearchQue
         }
     };
 
-    Ok(HttpResponse::Ok().json(Paginated::paginate(found, method)))
+    decorate(&mut found, me, &pool.get()?)?;
+
+    Ok(HttpResponse::Ok().json(Paginated::paginate(found, method, limit, &req)))
 }
 
-pub async fn feed(args: web::Path<SearchMethod>, query: web::Query<SearchQuery>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+pub async fn hidden(path: web::Path<SearchMethod>, query: web::Query<SearchQuery>, req: HttpRequest, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
     let me = get_identity(&ident)?.userid;
 
-    let method = args.into_inner();
-    let (id, limit) = query.into_inner().into_parts();
+    let method = path.into_inner();
+    let Page { method, key, limit } = query.into_inner().page::<i64>(method)?;
 
-    let found: Vec<Entry> = {
-        use crate::views::visible_entries::dsl::*;
+    let mut found: Vec<Entry> = {
+        use crate::views::hidden_entries::dsl::*;
 
         match method {
             SearchMethod::Before => {
-                visible_entries
-                    .filter(entryid.lt(id).and(can_see(me, author, journal)))
+                hidden_entries
+                    .filter(entryid.lt(key).and(author.eq(me)))
                     .order(entryid.desc())
                     .limit(limit)
                     .get_results(&pool.get()?)?
             },
             SearchMethod::After => {
-                visible_entries
-                    .filter(entryid.gt(id).and(can_see(me, author, journal)))
+                hidden_entries
+                    .filter(entryid.gt(key).and(author.eq(me)))
                     .order(entryid.asc())
                     .limit(limit)
                     .get_results(&pool.get()?)?
@@ -68,5 +175,246 @@ pub async fn feed(args: web::Path<SearchMethod>, query: web::Query<SearchQuery>,
         }
     };
 
-    Ok(HttpResponse::Ok().json(Paginated::paginate(found, method)))
-}
\ No newline at end of file
+    decorate(&mut found, me, &pool.get()?)?;
+
+    Ok(HttpResponse::Ok().json(Paginated::paginate(found, method, limit, &req)))
+}
+
+pub async fn feed(args: web::Path<SearchMethod>, query: web::Query<SearchQuery>, options: web::Query<FeedQuery>, filter: web::Query<EntryFilter>, req: HttpRequest, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    let method = args.into_inner();
+    let db = pool.get()?;
+
+    match options.order {
+        FeedOrder::Chronological => chronological(me, method, query.into_inner(), filter.into_inner(), &req, &db),
+        FeedOrder::Ranked => ranked(me, method, query.into_inner(), filter.into_inner(), &req, &db)
+    }
+}
+
+fn chronological(me: i64, method: SearchMethod, query: SearchQuery, filter: EntryFilter, req: &HttpRequest, db: &PgConnection) -> RequestResult {
+    let Page { method, key, limit } = query.page::<i64>(method)?;
+
+    let mut found: Vec<Entry> = {
+        use crate::views::visible_entries::dsl::*;
+
+        let filtered = filter.apply(visible_entries.into_boxed())?;
+        let visible = visible_to(me, db)?;
+
+        match method {
+            SearchMethod::Before => {
+                filtered
+                    .filter(entryid.lt(key).and(visible).and(published.or(author.eq(me))))
+                    .order(entryid.desc())
+                    .limit(limit)
+                    .get_results(db)?
+            },
+            SearchMethod::After => {
+                filtered
+                    .filter(entryid.gt(key).and(visible).and(published.or(author.eq(me))))
+                    .order(entryid.asc())
+                    .limit(limit)
+                    .get_results(db)?
+            }
+        }
+    };
+
+    decorate(&mut found, me, db)?;
+
+    Ok(HttpResponse::Ok().json(Paginated::paginate(found, method, limit, req)))
+}
+
+/// Scores published entries from the last `RANKED_WINDOW` days as of the first page, which the cursors
+/// carry along so that entries don't shuffle between pages as reactions come in.
+fn ranked(me: i64, method: SearchMethod, query: SearchQuery, filter: EntryFilter, req: &HttpRequest, db: &PgConnection) -> RequestResult {
+    let Page { method, key: (as_of, score, eid), limit } = query.page::<(Option<chrono::NaiveDateTime>, u64, i64)>(method)?;
+    let score = f64::from_bits(score);
+
+    let as_of = match as_of {
+        Some(as_of) => as_of,
+        None => diesel::select(now).get_result(db)?
+    };
+
+    let candidates: Vec<i64> = {
+        use crate::views::visible_entries::dsl::*;
+
+        let window = created.le(as_of).and(created.gt(as_of - chrono::Duration::days(RANKED_WINDOW)));
+        let visible = published.and(visible_to(me, db)?);
+
+        filter.apply(visible_entries.into_boxed())?
+            .select(entryid)
+            .filter(window.and(visible))
+            .get_results(db)?
+    };
+
+    // every candidate is scored in one go, and the page is cut from the scores rather than scoring again to compare
+    let page = match method {
+        SearchMethod::Before => "select entry, score from feed_scores($1, $2, $3) where (score, entry) < ($4, $5) order by score desc, entry desc limit $6",
+        SearchMethod::After => "select entry, score from feed_scores($1, $2, $3) where (score, entry) > ($4, $5) order by score asc, entry asc limit $6"
+    };
+
+    let scores: Vec<Score> = diesel::sql_query(page)
+        .bind::<BigInt, _>(me)
+        .bind::<Array<BigInt>, _>(&candidates)
+        .bind::<Timestamp, _>(as_of)
+        .bind::<Double, _>(score)
+        .bind::<BigInt, _>(eid)
+        .bind::<BigInt, _>(limit)
+        .load(db)?;
+
+    let mut entries: Vec<Entry> = {
+        use crate::views::visible_entries::dsl::*;
+
+        visible_entries
+            .filter(entryid.eq_any(scores.iter().map(|s| s.entry).collect::<Vec<_>>()))
+            .get_results(db)?
+    };
+
+    decorate(&mut entries, me, db)?;
+
+    let ranked: Vec<Ranked> = scores.into_iter()
+        .filter_map(|Score { entry: id, score }| {
+            let at = entries.iter().position(|entry| entry.id == id)?;
+            Some(Ranked { entry: entries.swap_remove(at), score, as_of })
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(Paginated::paginate(ranked, method, limit, req)))
+}
+
+/// Entries written on today's date in earlier years, as far as the user's timezone is concerned,
+/// grouped by year with the most recent first.
+pub async fn memories(query: web::Query<MemoriesQuery>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    let db = pool.get()?;
+
+    let tz = get_timezone(me, &db)?;
+
+    let found: Vec<(Entry, chrono::NaiveDate)> = {
+        use crate::views::visible_entries::{all_columns, dsl::*};
+
+        let remembered = visible_entries
+            .select((all_columns, local_date(created, tz.as_str())))
+            .filter(on_this_day(created, tz.as_str()))
+            .into_boxed();
+
+        let remembered = if query.friends {
+            remembered.filter(author.eq(me).or(are_friends(me, author).and(published).and(can_see(me, author, journal))))
+        } else {
+            remembered.filter(author.eq(me))
+        };
+
+        remembered
+            .order(created.desc())
+            .limit(MAX_MEMORIES)
+            .get_results(&db)?
+    };
+
+    let (mut entries, dates): (Vec<Entry>, Vec<chrono::NaiveDate>) = found.into_iter().unzip();
+    decorate(&mut entries, me, &db)?;
+
+    let mut years: Vec<Memories> = Vec::new();
+
+    for (entry, date) in entries.into_iter().zip(dates) {
+        match years.last_mut() {
+            Some(memories) if memories.year == date.year() => memories.entries.push(entry),
+            _ => years.push(Memories { year: date.year(), entries: vec![entry] })
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(years))
+}
+
+/// Searches the content and tags of the entries the user can see, with the best matches first
+/// when searching before. Searches are parsed like a web search, so quoted phrases, `or` and `-word` work.
+pub async fn search(path: web::Path<SearchMethod>, query: web::Query<SearchQuery>, text: web::Query<TextQuery>, filter: web::Query<EntryFilter>, req: HttpRequest, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    let q = text.into_inner().q;
+    let q = q.trim();
+
+    if q.is_empty() {
+        return Err(Error::BadRequest("search is empty".into()));
+    }
+
+    if q.chars().count() > MAX_QUERY_LENGTH {
+        return Err(Error::BadRequest(format!("searches are limited to {} characters", MAX_QUERY_LENGTH)));
+    }
+
+    let method = path.into_inner();
+    let Page { method, key: (score, eid), limit } = query.into_inner().page::<(u32, i64)>(method)?;
+    let score = f32::from_bits(score);
+
+    let db = pool.get()?;
+
+    let found: Vec<(Entry, f32, String)> = {
+        use crate::schema::entry_search;
+        use crate::views::visible_entries::{all_columns, dsl::*};
+
+        let matching = entry_search::table
+            .filter(entry_matches(entry_search::document, q))
+            .select(entry_search::entry);
+
+        let rank = entry_rank(entryid, q);
+        let visible = can_see(me, author, journal).and(published.or(author.eq(me)));
+        let filtered = filter.into_inner().apply(visible_entries.into_boxed())?;
+
+        match method {
+            SearchMethod::Before => {
+                filtered
+                    .select((all_columns, rank, entry_snippet(content, q)))
+                    .filter(entryid.eq_any(matching).and(visible))
+                    .filter(rank.lt(score).or(rank.eq(score).and(entryid.lt(eid))))
+                    .order((rank.desc(), entryid.desc()))
+                    .limit(limit)
+                    .get_results(&db)?
+            },
+            SearchMethod::After => {
+                filtered
+                    .select((all_columns, rank, entry_snippet(content, q)))
+                    .filter(entryid.eq_any(matching).and(visible))
+                    .filter(rank.gt(score).or(rank.eq(score).and(entryid.gt(eid))))
+                    .order((rank.asc(), entryid.asc()))
+                    .limit(limit)
+                    .get_results(&db)?
+            }
+        }
+    };
+
+    let mut entries = Vec::with_capacity(found.len());
+    let mut matched = Vec::with_capacity(found.len());
+
+    for (entry, rank, snippet) in found {
+        entries.push(entry);
+        matched.push((rank, snippet));
+    }
+
+    decorate(&mut entries, me, &db)?;
+
+    let matches: Vec<Match> = entries.into_iter()
+        .zip(matched)
+        .map(|(entry, (rank, snippet))| Match { entry, rank, snippet })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(Paginated::paginate(matches, method, limit, &req)))
+}
+
+/// Which entries the user can see, answered from their materialized feed unless it is waiting
+/// to be rebuilt, in which case it falls back to `can_see`.
+fn visible_to(me: i64, db: &PgConnection) -> ValyouResult<Box<dyn BoxableExpression<visible_entries::table, Pg, SqlType = Bool>>> {
+    use crate::schema::{feed_invalidations, feed_items};
+    use crate::views::visible_entries::dsl::*;
+
+    let stale: bool = diesel::select(exists(feed_invalidations::table.find(me))).get_result(db)?;
+
+    if stale {
+        Ok(Box::new(can_see(me, author, journal)))
+    } else {
+        let items = feed_items::table
+            .filter(feed_items::userid.eq(me))
+            .select(feed_items::entry);
+
+        Ok(Box::new(entryid.eq_any(items)))
+    }
+}
@@ -1,23 +1,28 @@
 use std::cmp::min;
 
 use actix_identity::Identity;
-use actix_web::{HttpResponse, Responder, web};
+use actix_web::{HttpRequest, HttpResponse, Responder, web};
 use diesel::prelude::*;
 
 use crate::errors::RequestResult;
-use crate::models::can_see;
+use crate::models::{self, can_see};
 use crate::models::Journal;
-use crate::models::search::{Paginated, SearchMethod, SearchQuery};
+use crate::models::search::{Page, Paginated, SearchMethod, SearchQuery};
 use crate::models::visibility::Visibility;
+use crate::models::webhooks::WebhookEvent;
 use crate::Pool;
 use crate::routes::account::get_identity;
+use crate::routes::webhooks::enqueue;
 use crate::schema::journals;
 
 #[derive(Debug, Deserialize)]
 pub struct CreateRequest {
     pub title: String,
     pub description: Option<String>,
-    pub visibility: Option<Visibility>
+    pub visibility: Option<Visibility>,
+    #[serde(default, deserialize_with = "models::edit_window_serde::deserialize_some")]
+    pub edit_window: Option<Option<i32>>,
+    pub comments_enabled: Option<bool>
 }
 
 #[derive(Debug, Insertable)]
@@ -26,7 +31,9 @@ pub struct NewJournal {
     pub owner: i64,
     pub title: String,
     pub description: Option<String>,
-    pub visibility: Visibility
+    pub visibility: Visibility,
+    pub edit_window: Option<i32>,
+    pub comments_enabled: bool
 }
 
 #[derive(Debug, Deserialize, AsChangeset)]
@@ -34,18 +41,23 @@ pub struct NewJournal {
 pub struct EditRequest {
     pub title: Option<String>,
     pub description: Option<String>,
-    pub visibility: Option<Visibility>
+    pub visibility: Option<Visibility>,
+    #[serde(default, deserialize_with = "models::edit_window_serde::deserialize_some")]
+    pub edit_window: Option<Option<i32>>,
+    pub comments_enabled: Option<bool>
 }
 
 pub async fn create(create: web::Json<CreateRequest>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
     let identity = get_identity(&ident)?;
-    let CreateRequest { title, description, visibility } = create.into_inner();
+    let CreateRequest { title, description, visibility, edit_window, comments_enabled } = create.into_inner();
 
     let new_journal = NewJournal {
         owner: identity.userid,
         title,
         description,
-        visibility: visibility.unwrap_or(Visibility::Private)
+        visibility: visibility.unwrap_or(Visibility::Private),
+        edit_window: edit_window.unwrap_or(Some(models::DEFAULT_EDIT_WINDOW)),
+        comments_enabled: comments_enabled.unwrap_or(true)
     };
 
     let db = pool.get()?;
@@ -65,11 +77,15 @@ pub async fn edit(path: web::Path<i64>, json: web::Json<EditRequest>, ident: Ide
     let jid = path.into_inner();
     let me = get_identity(&ident)?.userid;
 
+    let db = pool.get()?;
+
     use self::journals::dsl::*;
     let journal: Journal = diesel::update(journals)
         .filter(journalid.eq(jid).and(owner.eq(me)))
         .set(json.into_inner())
-        .get_result(&pool.get()?)?;
+        .get_result(&db)?;
+
+    enqueue(me, WebhookEvent::JournalUpdated, json!({ "journal": format!("{:019}", jid) }), &db)?;
 
     Ok(HttpResponse::Ok().json(journal))
 }
@@ -87,58 +103,58 @@ pub async fn find(path: web::Path<i64>, ident: Identity, pool: web::Data<Pool>)
     Ok(HttpResponse::Ok().json(found))
 }
 
-pub async fn get_own_journals(path: web::Path<SearchMethod>, query: web::Query<SearchQuery>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+pub async fn get_own_journals(path: web::Path<SearchMethod>, query: web::Query<SearchQuery>, req: HttpRequest, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
     let me = get_identity(&ident)?.userid;
 
     let method = path.into_inner();
-    let (id, limit) = query.into_inner().into_parts();
+    let Page { method, key, limit } = query.into_inner().page::<i64>(method)?;
 
     use self::journals::dsl::*;
     let found: Vec<Journal> = match method {
         SearchMethod::Before => {
             journals
-                .filter(journalid.lt(id).and(owner.eq(me)))
+                .filter(journalid.lt(key).and(owner.eq(me)))
                 .order(journalid.desc())
                 .limit(limit)
                 .get_results(&pool.get()?)?
         },
         SearchMethod::After => {
             journals
-                .filter(journalid.gt(id).and(owner.eq(me)))
+                .filter(journalid.gt(key).and(owner.eq(me)))
                 .order(journalid.asc())
                 .limit(limit)
                 .get_results(&pool.get()?)?
         }
     };
 
-    Ok(HttpResponse::Ok().json(Paginated::paginate(found, method)))
+    Ok(HttpResponse::Ok().json(Paginated::paginate(found, method, limit, &req)))
 }
 
-pub async fn get_user_journals(path: web::Path<(i64, SearchMethod)>, query: web::Query<SearchQuery>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+pub async fn get_user_journals(path: web::Path<(i64, SearchMethod)>, query: web::Query<SearchQuery>, req: HttpRequest, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
     let me = get_identity(&ident)?.userid;
 
     let (user, method) = path.into_inner();
-    let (id, limit) = query.into_inner().into_parts();
+    let Page { method, key, limit } = query.into_inner().page::<i64>(method)?;
 
     use self::journals::dsl::*;
     let found: Vec<Journal> = match method {
         SearchMethod::Before => {
             journals
-                .filter(journalid.lt(id).and(owner.eq(user)).and(can_see(me, user, journalid)))
+                .filter(journalid.lt(key).and(owner.eq(user)).and(can_see(me, user, journalid)))
                 .order(journalid.desc())
                 .limit(limit)
                 .get_results(&pool.get()?)?
         },
         SearchMethod::After => {
             journals
-                .filter(journalid.gt(id).and(owner.eq(user)).and(can_see(me, user, journalid)))
+                .filter(journalid.gt(key).and(owner.eq(user)).and(can_see(me, user, journalid)))
                 .order(journalid.asc())
                 .limit(limit)
                 .get_results(&pool.get()?)?
         }
     };
 
-    Ok(HttpResponse::Ok().json(Paginated::paginate(found, method)))
+    Ok(HttpResponse::Ok().json(Paginated::paginate(found, method, limit, &req)))
 }
 
 pub async fn search(ident: Identity) -> impl Responder {
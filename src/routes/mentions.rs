@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+
+use crate::errors::ValyouResult;
+use crate::events::Events;
+use crate::models::can_see;
+use crate::models::mentions::{self, Mention};
+use crate::models::notifications::NotificationKind;
+use crate::routes::notifications::notify;
+use crate::schema::{entries, entry_mentions, usernames};
+
+/// Resolves the handles in an entry's content and replaces its stored mentions.
+/// Handles of users who can't see the entry are left unresolved, and newly mentioned users are notified.
+pub fn save(eid: i64, content: &str, events: &Events, db: &PgConnection) -> ValyouResult<()> {
+    let candidates = mentions::parse(content);
+
+    let (author, journal): (i64, i64) = entries::table
+        .select((entries::author, entries::journal))
+        .filter(entries::entryid.eq(eid))
+        .get_result(db)?;
+
+    let handles: Vec<(i64, String, i16)> = if candidates.is_empty() {
+        Vec::new()
+    } else {
+        let names: Vec<&str> = candidates.iter()
+            .flat_map(|c| c.names.iter().map(|(_, name)| name.as_str()))
+            .collect();
+        let discriminators: Vec<i16> = candidates.iter().map(|c| c.discriminator).collect();
+
+        usernames::table
+            .filter(usernames::username.eq_any(names).and(usernames::discriminator.eq_any(discriminators)))
+            .filter(can_see(usernames::userid, author, journal))
+            .select((usernames::userid, usernames::username, usernames::discriminator))
+            .get_results(db)?
+    };
+
+    let resolved: Vec<(i64, usize, usize)> = candidates.iter()
+        .filter_map(|c| c.names.iter().find_map(|(start, name)| {
+            handles.iter()
+                .find(|(_, username, discriminator)| username == name && *discriminator == c.discriminator)
+                .map(|(user, _, _)| (*user, *start, c.end - start))
+        }))
+        .collect();
+
+    let previous: Vec<i64> = diesel::delete(entry_mentions::table)
+        .filter(entry_mentions::entry.eq(eid))
+        .returning(entry_mentions::userid)
+        .get_results(db)?;
+
+    if !resolved.is_empty() {
+        let rows: Vec<_> = resolved.iter()
+            .map(|&(user, start, length)| (
+                entry_mentions::entry.eq(eid),
+                entry_mentions::userid.eq(user),
+                entry_mentions::start.eq(start as i32),
+                entry_mentions::length.eq(length as i32)
+            ))
+            .collect();
+
+        diesel::insert_into(entry_mentions::table)
+            .values(&rows)
+            .execute(db)?;
+    }
+
+    let mut notified = previous;
+
+    for &(user, _, _) in &resolved {
+        if !notified.contains(&user) {
+            notify(user, NotificationKind::Mention, author, Some(eid), None, events, db)?;
+            notified.push(user);
+        }
+    }
+
+    Ok(())
+}
+
+/// Loads the mentions in each of the given entries, leaving out anyone who can no longer see them.
+pub fn load(ids: &[i64], db: &PgConnection) -> ValyouResult<HashMap<i64, Vec<Mention>>> {
+    let found: Vec<(i64, Mention)> = entry_mentions::table
+        .inner_join(entries::table)
+        .filter(entry_mentions::entry.eq_any(ids))
+        .filter(can_see(entry_mentions::userid, entries::author, entries::journal))
+        .order(entry_mentions::start.asc())
+        .select((entry_mentions::entry, (entry_mentions::userid, entry_mentions::start, entry_mentions::length)))
+        .get_results(db)?;
+
+    let mut mentions: HashMap<i64, Vec<Mention>> = HashMap::new();
+
+    for (eid, mention) in found {
+        mentions.entry(eid).or_default().push(mention);
+    }
+
+    Ok(mentions)
+}
@@ -0,0 +1,141 @@
+use actix_identity::Identity;
+use actix_web::{HttpRequest, HttpResponse, web};
+use diesel::prelude::*;
+
+use crate::errors::{Error, RequestResult, ValyouResult};
+use crate::events::{Event, Events};
+use crate::models::are_blocked;
+use crate::models::messages::{Conversation, Message};
+use crate::models::search::{Page, Paginated, SearchMethod, SearchQuery};
+use crate::Pool;
+use crate::routes::account::get_identity;
+
+#[derive(Debug, Deserialize)]
+pub struct SendRequest {
+    pub content: String
+}
+
+/// Lists the user's conversations, most recently active first when searching before an id.
+pub async fn conversations(path: web::Path<SearchMethod>, query: web::Query<SearchQuery>, req: HttpRequest, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    let method = path.into_inner();
+    let Page { method, key, limit } = query.into_inner().page::<i64>(method)?;
+
+    let found: Vec<Conversation> = {
+        use crate::views::conversations::dsl::*;
+
+        let columns = (other, username, discriminator, last_message, last_sender, created, content, unread);
+
+        match method {
+            SearchMethod::Before => {
+                conversations
+                    .select(columns)
+                    .filter(last_message.lt(key).and(userid.eq(me)))
+                    .order(last_message.desc())
+                    .limit(limit)
+                    .get_results(&pool.get()?)?
+            },
+            SearchMethod::After => {
+                conversations
+                    .select(columns)
+                    .filter(last_message.gt(key).and(userid.eq(me)))
+                    .order(last_message.asc())
+                    .limit(limit)
+                    .get_results(&pool.get()?)?
+            }
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(Paginated::paginate(found, method, limit, &req)))
+}
+
+pub async fn list(path: web::Path<(i64, SearchMethod)>, query: web::Query<SearchQuery>, req: HttpRequest, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    let (other, method) = path.into_inner();
+    let Page { method, key, limit } = query.into_inner().page::<i64>(method)?;
+
+    let db = pool.get()?;
+
+    check_not_blocked(me, other, &db)?;
+
+    let found: Vec<Message> = {
+        use crate::schema::messages::dsl::*;
+
+        let between = sender.eq(me).and(recipient.eq(other))
+            .or(sender.eq(other).and(recipient.eq(me)));
+
+        match method {
+            SearchMethod::Before => {
+                messages
+                    .filter(messageid.lt(key).and(between))
+                    .order(messageid.desc())
+                    .limit(limit)
+                    .get_results(&db)?
+            },
+            SearchMethod::After => {
+                messages
+                    .filter(messageid.gt(key).and(between))
+                    .order(messageid.asc())
+                    .limit(limit)
+                    .get_results(&db)?
+            }
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(Paginated::paginate(found, method, limit, &req)))
+}
+
+pub async fn send(path: web::Path<i64>, json: web::Json<SendRequest>, ident: Identity, pool: web::Data<Pool>, events: web::Data<Events>) -> RequestResult {
+    let other = path.into_inner();
+    let me = get_identity(&ident)?.userid;
+
+    if me == other {
+        return Err(Error::BadRequest("provided own userid".into()));
+    }
+
+    let db = pool.get()?;
+
+    let message: Message = {
+        use crate::schema::messages::dsl::*;
+
+        diesel::insert_into(messages)
+            .values(&(sender.eq(me), recipient.eq(other), content.eq(json.into_inner().content)))
+            .get_result(&db)?
+    };
+
+    // the message is already sent, so a failed event is only logged
+    if let Err(e) = events.publish(other, Event::Message { id: message.id, from: me }, &db) {
+        log::error!("could not publish message event: {}", e);
+    }
+
+    Ok(HttpResponse::Created().json(message))
+}
+
+/// Marks everything the other user has sent as read.
+pub async fn mark_read(path: web::Path<i64>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let other = path.into_inner();
+    let me = get_identity(&ident)?.userid;
+
+    let db = pool.get()?;
+
+    check_not_blocked(me, other, &db)?;
+
+    use crate::schema::messages::dsl::*;
+    diesel::update(messages)
+        .filter(sender.eq(other).and(recipient.eq(me)).and(read.eq(false)))
+        .set(read.eq(true))
+        .execute(&db)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Conversations disappear for both users once either blocks the other.
+fn check_not_blocked(me: i64, other: i64, db: &PgConnection) -> ValyouResult<()> {
+    if diesel::select(are_blocked(me, other)).get_result::<bool>(db)? {
+        Err(Error::NotFound)
+    } else {
+        Ok(())
+    }
+}
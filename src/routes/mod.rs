@@ -3,4 +3,16 @@ pub mod journals;
 pub mod account;
 pub mod profiles;
 pub mod feed;
-pub mod relationships;
\ No newline at end of file
+pub mod relationships;
+pub mod revisions;
+pub mod attachments;
+pub mod reactions;
+pub mod comments;
+pub mod mentions;
+pub mod notifications;
+pub mod events;
+pub mod digests;
+pub mod messages;
+pub mod webhooks;
+pub mod calendar;
+pub mod stats;
\ No newline at end of file
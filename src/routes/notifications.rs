@@ -0,0 +1,136 @@
+use std::collections::BTreeMap;
+
+use actix_identity::Identity;
+use actix_web::{HttpRequest, HttpResponse, web};
+use diesel::prelude::*;
+
+use crate::errors::{Error, RequestResult, ValyouResult};
+use crate::events::{Event, Events};
+use crate::models;
+use crate::models::notifications::{Notification, NotificationKind};
+use crate::models::search::{Page, Paginated, SearchMethod, SearchQuery};
+use crate::Pool;
+use crate::routes::account::get_identity;
+
+/// Whether each kind of notification is turned on.
+pub type Preferences = BTreeMap<NotificationKind, bool>;
+
+pub async fn inbox(path: web::Path<SearchMethod>, query: web::Query<SearchQuery>, req: HttpRequest, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    let method = path.into_inner();
+    let Page { method, key, limit } = query.into_inner().page::<i64>(method)?;
+
+    let found: Vec<Notification> = {
+        use crate::views::inbox::dsl::*;
+
+        let columns = (notificationid, kind, actor, entry, journal, comment, created, read);
+
+        match method {
+            SearchMethod::Before => {
+                inbox
+                    .select(columns)
+                    .filter(notificationid.lt(key).and(userid.eq(me)))
+                    .order(notificationid.desc())
+                    .limit(limit)
+                    .get_results(&pool.get()?)?
+            },
+            SearchMethod::After => {
+                inbox
+                    .select(columns)
+                    .filter(notificationid.gt(key).and(userid.eq(me)))
+                    .order(notificationid.asc())
+                    .limit(limit)
+                    .get_results(&pool.get()?)?
+            }
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(Paginated::paginate(found, method, limit, &req)))
+}
+
+pub async fn mark_read(path: web::Path<i64>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let nid = path.into_inner();
+    let me = get_identity(&ident)?.userid;
+
+    use crate::schema::notifications::dsl::*;
+    let updated = diesel::update(notifications)
+        .filter(notificationid.eq(nid).and(userid.eq(me)))
+        .set(read.eq(true))
+        .execute(&pool.get()?)?;
+
+    if updated == 0 {
+        return Err(Error::NotFound);
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn mark_all_read(ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    use crate::schema::notifications::dsl::*;
+    diesel::update(notifications)
+        .filter(userid.eq(me).and(read.eq(false)))
+        .set(read.eq(true))
+        .execute(&pool.get()?)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn preferences(ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    Ok(HttpResponse::Ok().json(load_preferences(me, &pool.get()?)?))
+}
+
+/// Only the kinds of notification present in the request are changed.
+pub async fn edit_preferences(json: web::Json<Preferences>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    let db = pool.get()?;
+
+    {
+        use crate::schema::notification_preferences::dsl::*;
+
+        for (&what, &value) in json.iter() {
+            diesel::insert_into(notification_preferences)
+                .values(&(userid.eq(me), kind.eq(what), enabled.eq(value)))
+                .on_conflict((userid, kind))
+                .do_update()
+                .set(enabled.eq(value))
+                .execute(&db)?;
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(load_preferences(me, &db)?))
+}
+
+/// Sends a notification to a user, unless they caused it themselves or have turned that kind off.
+pub fn notify(recipient: i64, what: NotificationKind, actor: i64, entry: Option<i64>, comment: Option<i64>, events: &Events, db: &PgConnection) -> ValyouResult<()> {
+    let sent: Option<i64> = diesel::select(models::notify(recipient, what, actor, entry, comment))
+        .get_result(db)?;
+
+    if let Some(id) = sent {
+        // the notification is already saved, so a failed event is only logged
+        if let Err(e) = events.publish(recipient, Event::Notification { id, kind: what }, db) {
+            log::error!("could not publish notification event: {}", e);
+        }
+    }
+
+    Ok(())
+}
+
+fn load_preferences(me: i64, db: &PgConnection) -> ValyouResult<Preferences> {
+    use crate::schema::notification_preferences::dsl::*;
+
+    let saved: Vec<(NotificationKind, bool)> = notification_preferences
+        .filter(userid.eq(me))
+        .select((kind, enabled))
+        .get_results(db)?;
+
+    let mut found: Preferences = NotificationKind::ALL.iter().map(|&k| (k, true)).collect();
+    found.extend(saved);
+
+    Ok(found)
+}
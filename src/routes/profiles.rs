@@ -1,17 +1,25 @@
-use std::cmp::min;
-
 use actix_identity::Identity;
-use actix_web::{HttpResponse, web};
+use actix_web::{HttpMessage, HttpRequest, HttpResponse, web};
+use diesel::dsl::{self, Find, not, Select};
+use diesel::expression::AsExpression;
+use diesel::pg::Pg;
 use diesel::prelude::*;
+use diesel::query_builder::{AsChangeset, QueryFragment};
+use diesel::query_dsl::LoadQuery;
+use diesel::query_dsl::methods::SelectDsl;
 
-use crate::errors::{RequestResult, ValyouResult};
-use crate::models::{self, can_see, can_see_user, Journal};
-use crate::models::profiles::{FullProfile, Profile};
-use crate::models::search::{Paginated, SearchMethod};
+use crate::errors::{Error, RequestResult, ValyouResult};
+use crate::models::{self, are_blocked, are_friends, can_see, can_see_user, id_generator, Journal, user_matches, user_score};
+use crate::models::profiles::{avatar_key, AVATAR_SIZES, BANNER_SIZE, banner_key, DEFAULT_AVATAR_SIZE, Found, FullProfile, MAX_SEARCH_LENGTH, Profile};
+use crate::models::search::{default_limit, Page, Paginated, SearchMethod, SearchQuery};
 use crate::models::visibility::Visibility;
 use crate::Pool;
 use crate::routes::account::get_identity;
 use crate::schema::profiles;
+use crate::storage::{images, read, Storage};
+
+/// How many of a user's newest journals are shown with their profile.
+const PROFILE_JOURNALS: i64 = 10;
 
 #[derive(Debug, Deserialize, AsChangeset)]
 #[table_name = "profiles"]
@@ -19,6 +27,8 @@ pub struct EditRequest {
     pub summary: Option<String>,
     pub bio: Option<String>,
     pub visibility: Option<Visibility>,
+    /// An IANA timezone such as `Europe/Budapest`, which the user's days are counted in.
+    pub timezone: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -29,7 +39,21 @@ pub struct ChangeUsername {
 #[derive(Debug, Deserialize)]
 pub struct Search {
     pub q: String,
-    pub count: i64
+    /// Also matches summaries, though never as well as a matching username.
+    #[serde(default)]
+    pub summaries: bool
+}
+
+/// How many users the unpaged search returns.
+#[derive(Debug, Deserialize)]
+pub struct Count {
+    #[serde(alias = "count", default = "default_limit")]
+    pub limit: i64
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AvatarQuery {
+    pub size: Option<u32>
 }
 
 #[derive(Debug, Serialize)]
@@ -71,15 +95,28 @@ pub async fn view_self(ident: Identity, pool: web::Data<Pool>) -> RequestResult
         use crate::schema::journals::dsl::*;
         let out: Vec<Journal> = journals
             .filter(owner.eq(me))
-            .limit(10)
+            .order(journalid.desc())
+            .limit(PROFILE_JOURNALS + 1)
             .get_results(&pool.get()?)?;
 
-        Paginated::paginate(out, SearchMethod::Before)
+        Paginated::paginate_at(out, SearchMethod::Before, PROFILE_JOURNALS + 1, "/user/self/journals", "")
     };
 
     Ok(HttpResponse::Ok().json(ProfileResponse { profile, journals }))
 }
 
+/// The timezone the user's days are counted in.
+pub fn get_timezone(user: i64, db: &PgConnection) -> ValyouResult<String> {
+    use crate::schema::profiles::dsl::*;
+
+    let tz = profiles
+        .find(user)
+        .select(timezone)
+        .get_result(db)?;
+
+    Ok(tz)
+}
+
 fn get_profile(user: i64, pool: &web::Data<Pool>) -> ValyouResult<FullProfile> {
     use crate::views::full_profiles::dsl::*;
     let profile: FullProfile = full_profiles
@@ -89,44 +126,292 @@ fn get_profile(user: i64, pool: &web::Data<Pool>) -> ValyouResult<FullProfile> {
     Ok(profile)
 }
 
-pub async fn search(query: web::Query<Search>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+/// Finds other users by name, best matches first when searching before. A full `name#1234` handle
+/// only ever finds that user, anything else matches names by prefix and similarity, with friends first.
+pub async fn search(path: web::Path<SearchMethod>, query: web::Query<SearchQuery>, search: web::Query<Search>, req: HttpRequest, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
     let me = get_identity(&ident)?.userid;
 
-    let Search { q, count } = query.into_inner();
+    let (found, method, limit) = find_users(me, search.into_inner(), query.into_inner(), path.into_inner(), &pool.get()?)?;
+
+    Ok(HttpResponse::Ok().json(Paginated::paginate(found, method, limit, &req)))
+}
+
+/// The search from before results were paged, which still returns the first page of best matches
+/// as a bare list of profiles, and still takes `count` as another name for `limit`.
+pub async fn search_first(search: web::Query<Search>, count: web::Query<Count>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    let query = SearchQuery { id: None, cursor: None, limit: count.limit };
+    let (found, _, limit) = find_users(me, search.into_inner(), query, SearchMethod::Before, &pool.get()?)?;
+
+    // the page is fetched with one extra profile to tell whether there are more
+    let profiles: Vec<Profile> = found.into_iter()
+        .take((limit - 1).max(0) as usize)
+        .map(|found| found.profile)
+        .collect();
+
+    Ok(HttpResponse::Ok().json(profiles))
+}
+
+/// Fetches a page of matching users along with where it was fetched from and its limit.
+fn find_users(me: i64, search: Search, query: SearchQuery, method: SearchMethod, db: &PgConnection) -> ValyouResult<(Vec<Found>, SearchMethod, i64)> {
+    let Search { q, summaries } = search;
+    let q = q.trim();
+
+    if q.is_empty() {
+        return Err(Error::BadRequest("search is empty".into()));
+    }
+
+    if q.chars().count() > MAX_SEARCH_LENGTH {
+        return Err(Error::BadRequest(format!("searches are limited to {} characters", MAX_SEARCH_LENGTH)));
+    }
+
+    let Page { method, key: (min_score, uid), limit } = query.page::<(u32, i64)>(method)?;
+    let min_score = f32::from_bits(min_score);
 
-    let q = format!("%{}%", q.replace('%', "\\%"));
+    let found: Vec<(Profile, f32, bool)> = {
+        use crate::views::searchable::{all_columns, dsl::*};
 
-    let count = min(count, 30);
+        let score = user_score(me, userid, username, summary, q, summaries);
 
-    use crate::views::searchable::dsl::*;
-    let results: Vec<Profile> = searchable
-        .filter(userid.ne(me).and(username.like(q).escape('\\')))
-        .limit(count)
-        .get_results(&pool.get()?)?;
+        let mut matching = searchable
+            .select((all_columns, score, are_friends(me, userid)))
+            .filter(userid.ne(me).and(not(are_blocked(me, userid))))
+            .into_boxed();
 
-    Ok(HttpResponse::Ok().json(results))
+        matching = match parse_handle(q) {
+            Some((name, tag)) => matching.filter(username.eq(name).and(discriminator.eq(tag))),
+            None => matching.filter(user_matches(username, summary, q, summaries))
+        };
+
+        match method {
+            SearchMethod::Before => {
+                matching
+                    .filter(score.lt(min_score).or(score.eq(min_score).and(userid.lt(uid))))
+                    .order((score.desc(), userid.desc()))
+                    .limit(limit)
+                    .get_results(db)?
+            },
+            SearchMethod::After => {
+                matching
+                    .filter(score.gt(min_score).or(score.eq(min_score).and(userid.gt(uid))))
+                    .order((score.asc(), userid.asc()))
+                    .limit(limit)
+                    .get_results(db)?
+            }
+        }
+    };
+
+    let found: Vec<Found> = found.into_iter()
+        .map(|(profile, score, friend)| Found { profile, score, friend })
+        .collect();
+
+    Ok((found, method, limit))
 }
 
 pub async fn view(path: web::Path<i64>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
     let me = get_identity(&ident)?.userid;
     let person = path.into_inner();
 
-    let profile: FullProfile = {
+    let mut profile: FullProfile = {
         use crate::views::full_profiles::dsl::*;
         full_profiles
             .filter(userid.eq(person).and(can_see_user(me, person)))
             .get_result(&pool.get()?)?
     };
 
+    if person != me {
+        profile.timezone = None;
+    }
+
     let journals: Paginated<Journal> = {
         use crate::schema::journals::dsl::*;
         let out: Vec<Journal> = journals
             .filter(owner.eq(person).and(can_see(me, person, journalid)))
-            .limit(10)
+            .order(journalid.desc())
+            .limit(PROFILE_JOURNALS + 1)
             .get_results(&pool.get()?)?;
 
-        Paginated::paginate(out, SearchMethod::Before)
+        let base = format!("/user/{}/journals", person);
+        Paginated::paginate_at(out, SearchMethod::Before, PROFILE_JOURNALS + 1, &base, "")
     };
 
     Ok(HttpResponse::Ok().json(ProfileResponse { profile, journals }))
 }
+
+pub async fn set_avatar(body: web::Bytes, req: HttpRequest, ident: Identity, pool: web::Data<Pool>, storage: web::Data<Storage>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    let upload = Some((body, req.content_type().to_owned()));
+    replace_image(me, upload, profiles::avatar, |user, version, (size, _)| avatar_key(user, version, size), &avatar_sizes(), &pool, &storage).await
+}
+
+pub async fn delete_avatar(ident: Identity, pool: web::Data<Pool>, storage: web::Data<Storage>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    replace_image(me, None, profiles::avatar, |user, version, (size, _)| avatar_key(user, version, size), &avatar_sizes(), &pool, &storage).await
+}
+
+pub async fn set_banner(body: web::Bytes, req: HttpRequest, ident: Identity, pool: web::Data<Pool>, storage: web::Data<Storage>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    let upload = Some((body, req.content_type().to_owned()));
+    replace_image(me, upload, profiles::banner, |user, version, _| banner_key(user, version), &[BANNER_SIZE], &pool, &storage).await
+}
+
+pub async fn delete_banner(ident: Identity, pool: web::Data<Pool>, storage: web::Data<Storage>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    replace_image(me, None, profiles::banner, |user, version, _| banner_key(user, version), &[BANNER_SIZE], &pool, &storage).await
+}
+
+/// Serves an avatar in the smallest stored size that is at least as big as the `size` asked for,
+/// see `AVATAR_SIZES`.
+pub async fn avatar(path: web::Path<i64>, query: web::Query<AvatarQuery>, ident: Identity, pool: web::Data<Pool>, storage: web::Data<Storage>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+    let person = path.into_inner();
+
+    let version: Option<i64> = {
+        use crate::views::full_profiles::dsl::*;
+        full_profiles
+            .select(avatar)
+            .filter(userid.eq(person).and(can_see_user(me, person)))
+            .get_result(&pool.get()?)?
+    };
+
+    let version = version.ok_or(Error::NotFound)?;
+
+    let wanted = query.size.unwrap_or(DEFAULT_AVATAR_SIZE);
+    let size = AVATAR_SIZES.iter()
+        .rev()
+        .find(|&&size| size >= wanted)
+        .unwrap_or(&AVATAR_SIZES[0]);
+
+    let data = read(&storage, avatar_key(person, version, *size)).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("image/png")
+        .body(data))
+}
+
+pub async fn banner(path: web::Path<i64>, ident: Identity, pool: web::Data<Pool>, storage: web::Data<Storage>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+    let person = path.into_inner();
+
+    let version: Option<i64> = {
+        use crate::views::full_profiles::dsl::*;
+        full_profiles
+            .select(banner)
+            .filter(userid.eq(person).and(can_see_user(me, person)))
+            .get_result(&pool.get()?)?
+    };
+
+    let version = version.ok_or(Error::NotFound)?;
+
+    let data = read(&storage, banner_key(person, version)).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("image/png")
+        .body(data))
+}
+
+/// Replaces one of the user's profile images with an upload cropped to each of `sizes`, or removes it when
+/// there is no upload, then removes the files of the version it replaced. Decoding, resizing and the files
+/// are all dealt with on the thread pool.
+async fn replace_image<C>(me: i64, upload: Option<(web::Bytes, String)>, column: C, key: fn(i64, i64, (u32, u32)) -> String, sizes: &[(u32, u32)], pool: &web::Data<Pool>, storage: &web::Data<Storage>) -> RequestResult
+    where C: Column<Table = profiles::table> + ExpressionMethods + Copy,
+          Option<i64>: AsExpression<C::SqlType>,
+          Find<profiles::table, i64>: SelectDsl<C>,
+          Select<Find<profiles::table, i64>, C>: LoadQuery<PgConnection, Option<i64>>,
+          dsl::Eq<C, Option<i64>>: AsChangeset<Target = profiles::table>,
+          <dsl::Eq<C, Option<i64>> as AsChangeset>::Changeset: QueryFragment<Pg> {
+    let db = pool.get()?;
+
+    let version = match upload {
+        Some((body, mime)) => {
+            let version: i64 = diesel::select(id_generator()).get_result(&db)?;
+
+            let storage = storage.get_ref().clone();
+            let sizes = sizes.to_vec();
+
+            web::block(move || {
+                let image = images::decode(&body, &mime)?;
+
+                for &(width, height) in &sizes {
+                    storage.put(&key(me, version, (width, height)), &images::cover(&image, width, height)?)?;
+                }
+
+                Ok::<_, Error>(())
+            }).await?;
+
+            Some(version)
+        },
+        None => None
+    };
+
+    // the row is locked while the old version is swapped out, so two replacements at once can't both
+    // read the same old version and leave the files of one of the new ones behind
+    let old: Option<i64> = db.transaction::<_, Error, _>(|| {
+        profiles::table.find(me).select(profiles::userid).for_update().execute(&db)?;
+
+        let old = profiles::table.find(me).select(column).get_result(&db)?;
+
+        diesel::update(profiles::table.find(me))
+            .set(column.eq(version))
+            .execute(&db)?;
+
+        Ok(old)
+    })?;
+
+    if let Some(old) = old {
+        let storage = storage.get_ref().clone();
+        let sizes = sizes.to_vec();
+
+        web::block(move || {
+            for &size in &sizes {
+                storage.delete(&key(me, old, size))?;
+            }
+
+            Ok::<_, Error>(())
+        }).await?;
+    }
+
+    Ok(HttpResponse::Ok().json(get_profile(me, pool)?))
+}
+
+/// Every avatar size as the square it is cropped to.
+fn avatar_sizes() -> Vec<(u32, u32)> {
+    AVATAR_SIZES.iter().map(|&size| (size, size)).collect()
+}
+
+/// Splits a full handle such as `name#1234` into its username and discriminator.
+fn parse_handle(q: &str) -> Option<(&str, i16)> {
+    let mut parts = q.rsplitn(2, '#');
+    let tag = parts.next()?;
+    let name = parts.next()?;
+
+    if name.is_empty() || tag.len() != 4 || !tag.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    tag.parse().ok().filter(|&tag| tag > 0).map(|tag| (name, tag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_full_handles() {
+        assert_eq!(parse_handle("alice#1234"), Some(("alice", 1234)));
+        assert_eq!(parse_handle("bob#0001"), Some(("bob", 1)));
+        assert_eq!(parse_handle("we#ird#0042"), Some(("we#ird", 42)));
+    }
+
+    #[test]
+    fn rejects_anything_else() {
+        for q in &["alice", "alice#", "#1234", "alice#123", "alice#12345", "alice#0000", "alice#12a4", "alice#+123", "alice#١٢٣٤"] {
+            assert_eq!(parse_handle(q), None, "{}", q);
+        }
+    }
+}
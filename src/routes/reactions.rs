@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use actix_identity::Identity;
+use actix_web::{HttpResponse, web};
+use diesel::dsl::count_star;
+use diesel::prelude::*;
+
+use crate::errors::{RequestResult, ValyouResult};
+use crate::events::Events;
+use crate::models::notifications::NotificationKind;
+use crate::models::reactions::{Reaction, ReactionCount};
+use crate::Pool;
+use crate::routes::account::get_identity;
+use crate::routes::entries::check_visible;
+use crate::routes::notifications::notify;
+
+pub async fn react(path: web::Path<(i64, i64, Reaction)>, ident: Identity, pool: web::Data<Pool>, events: web::Data<Events>) -> RequestResult {
+    let (jid, eid, kind) = path.into_inner();
+    let me = get_identity(&ident)?.userid;
+
+    let db = pool.get()?;
+
+    let author = check_visible(jid, eid, me, &db)?;
+
+    let added = {
+        use crate::schema::reactions::dsl::*;
+
+        diesel::insert_into(reactions)
+            .values(&(entry.eq(eid), userid.eq(me), reaction.eq(kind)))
+            .on_conflict_do_nothing()
+            .execute(&db)?
+    };
+
+    if added > 0 {
+        notify(author, NotificationKind::Reaction, me, Some(eid), None, &events, &db)?;
+    }
+
+    let mut counts = load_counts(&[eid], me, &db)?;
+
+    Ok(HttpResponse::Ok().json(counts.remove(&eid).unwrap_or_default()))
+}
+
+pub async fn unreact(path: web::Path<(i64, i64, Reaction)>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let (jid, eid, kind) = path.into_inner();
+    let me = get_identity(&ident)?.userid;
+
+    let db = pool.get()?;
+
+    check_visible(jid, eid, me, &db)?;
+
+    {
+        use crate::schema::reactions::dsl::*;
+
+        diesel::delete(reactions)
+            .filter(entry.eq(eid).and(userid.eq(me)).and(reaction.eq(kind)))
+            .execute(&db)?;
+    }
+
+    let mut counts = load_counts(&[eid], me, &db)?;
+
+    Ok(HttpResponse::Ok().json(counts.remove(&eid).unwrap_or_default()))
+}
+
+/// Counts the reactions on each of the given entries, noting which ones the user left themselves.
+pub fn load_counts(ids: &[i64], me: i64, db: &PgConnection) -> ValyouResult<HashMap<i64, Vec<ReactionCount>>> {
+    use crate::schema::reactions::dsl::*;
+
+    let totals: Vec<(i64, Reaction, i64)> = reactions
+        .filter(entry.eq_any(ids))
+        .group_by((entry, reaction))
+        .select((entry, reaction, count_star()))
+        .get_results(db)?;
+
+    let mine: Vec<(i64, Reaction)> = reactions
+        .filter(entry.eq_any(ids).and(userid.eq(me)))
+        .select((entry, reaction))
+        .get_results(db)?;
+
+    let mut counts: HashMap<i64, Vec<ReactionCount>> = HashMap::new();
+
+    for (eid, kind, count) in totals {
+        counts.entry(eid).or_default().push(ReactionCount {
+            reaction: kind,
+            count,
+            reacted: mine.contains(&(eid, kind))
+        });
+    }
+
+    for found in counts.values_mut() {
+        found.sort_by_key(|c| c.reaction);
+    }
+
+    Ok(counts)
+}
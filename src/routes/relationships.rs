@@ -1,15 +1,20 @@
 use actix_identity::Identity;
-use actix_web::{HttpResponse, web};
+use actix_web::{HttpRequest, HttpResponse, web};
 use diesel::prelude::*;
 
-use crate::errors::{Error, RequestResult};
+use crate::errors::{Error, RequestResult, ValyouResult};
+use crate::events::{Event, Events};
+use crate::models::notifications::NotificationKind;
 use crate::models::profiles::Friend;
-use crate::models::search::{Paginated, SearchMethod};
+use crate::models::search::{Page, Paginated, SearchMethod, SearchQuery};
 use crate::models::status::RelationStatus;
+use crate::models::webhooks::WebhookEvent;
 use crate::Pool;
 use crate::routes::account::get_identity;
+use crate::routes::notifications::notify;
+use crate::routes::webhooks::enqueue;
 
-pub async fn send_request(to: web::Path<i64>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+pub async fn send_request(to: web::Path<i64>, ident: Identity, pool: web::Data<Pool>, events: web::Data<Events>) -> RequestResult {
     let friendid = to.into_inner();
     let userid = get_identity(&ident)?.userid;
 
@@ -23,16 +28,28 @@ pub async fn send_request(to: web::Path<i64>, ident: Identity, pool: web::Data<P
         ((friendid, userid), RelationStatus::PendingSecondFirst)
     };
 
-    use crate::schema::relations::dsl::*;
+    let db = pool.get()?;
 
-    diesel::insert_into(relations)
-        .values(&(user_from.eq(pair.0), user_to.eq(pair.1), status.eq(pending)))
-        .execute(&pool.get()?)?;
+    {
+        use crate::schema::relations::dsl::*;
+
+        diesel::insert_into(relations)
+            .values(&(user_from.eq(pair.0), user_to.eq(pair.1), status.eq(pending)))
+            .execute(&db)?;
+    }
+
+    // the request is already made, so a failed event is only logged
+    if let Err(e) = events.publish(friendid, Event::FriendRequest { from: userid }, &db) {
+        log::error!("could not publish friend request event: {}", e);
+    }
+
+    notify(friendid, NotificationKind::FriendRequest, userid, None, None, &events, &db)?;
+    enqueue(friendid, WebhookEvent::FriendRequest, json!({ "from": format!("{:019}", userid) }), &db)?;
 
     Ok(HttpResponse::NoContent().finish())
 }
 
-pub async fn accept_request(path: web::Path<i64>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+pub async fn accept_request(path: web::Path<i64>, ident: Identity, pool: web::Data<Pool>, events: web::Data<Events>) -> RequestResult {
     let friendid = path.into_inner();
     let userid = get_identity(&ident)?.userid;
 
@@ -46,15 +63,18 @@ pub async fn accept_request(path: web::Path<i64>, ident: Identity, pool: web::Da
         ((friendid, userid), RelationStatus::PendingFirstSecond)
     };
 
+    let db = pool.get()?;
+
     let success: usize = {
         use crate::schema::relations::dsl::*;
         diesel::update(relations)
             .filter(user_from.eq(pair.0).and(user_to.eq(pair.1)).and(status.eq(required)))
             .set(status.eq(RelationStatus::Friends))
-            .execute(&pool.get()?)?
+            .execute(&db)?
     };
 
     if success > 0 {
+        notify(friendid, NotificationKind::FriendAccepted, userid, None, None, &events, &db)?;
         Ok(HttpResponse::NoContent().finish())
     } else {
         Err(Error::BadRequest("no request from that user".into()))
@@ -101,36 +121,122 @@ pub async fn remove_friend(to: web::Path<i64>, ident: Identity, pool: web::Data<
     Ok(HttpResponse::NoContent().finish())
 }
 
-pub async fn view_own_friends(ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+/// Pages through friends by when they became friends, most recent first when searching before.
+pub async fn view_own_friends(path: web::Path<SearchMethod>, query: web::Query<SearchQuery>, req: HttpRequest, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
     let me = get_identity(&ident)?.userid;
 
-    let friends: Vec<Friend> = {
+    let (found, method, limit) = friends_page(me, path.into_inner(), query.into_inner(), &pool.get()?)?;
+
+    Ok(HttpResponse::Ok().json(Paginated::paginate(found, method, limit, &req)))
+}
+
+/// The first page of friends, most recent first, which is all the list returned before it was paged.
+pub async fn first_friends(query: web::Query<SearchQuery>, req: HttpRequest, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    let (found, method, limit) = friends_page(me, SearchMethod::Before, query.into_inner(), &pool.get()?)?;
+
+    Ok(HttpResponse::Ok().json(Paginated::paginate_at(found, method, limit, req.path(), req.query_string())))
+}
+
+fn friends_page(me: i64, method: SearchMethod, query: SearchQuery, db: &PgConnection) -> ValyouResult<(Vec<Friend>, SearchMethod, i64)> {
+    let Page { method, key: (ts, fid), limit } = query.page::<(chrono::NaiveDateTime, i64)>(method)?;
+
+    let found: Vec<Friend> = {
         use crate::views::public_friends::dsl::*;
 
-        public_friends
-            .select((friend, username, discriminator, summary, bio, since))
-            .filter(userid.eq(me))
-            .order(friend.asc())
-            .get_results(&pool.get()?)?
+        let columns = (friend, username, discriminator, summary, bio, since, avatar, banner);
+
+        match method {
+            SearchMethod::Before => {
+                public_friends
+                    .select(columns)
+                    .filter(userid.eq(me))
+                    .filter(since.lt(ts).or(since.eq(ts).and(friend.lt(fid))))
+                    .order((since.desc(), friend.desc()))
+                    .limit(limit)
+                    .get_results(db)?
+            },
+            SearchMethod::After => {
+                public_friends
+                    .select(columns)
+                    .filter(userid.eq(me))
+                    .filter(since.gt(ts).or(since.eq(ts).and(friend.gt(fid))))
+                    .order((since.asc(), friend.asc()))
+                    .limit(limit)
+                    .get_results(db)?
+            }
+        }
     };
 
-    Ok(HttpResponse::Ok().json(Paginated::paginate(friends, SearchMethod::After)))
+    Ok((found, method, limit))
+}
+
+pub async fn show_requests(path: web::Path<SearchMethod>, query: web::Query<SearchQuery>, req: HttpRequest, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    let (found, method, limit) = requests_page(me, path.into_inner(), query.into_inner(), &pool.get()?)?;
+
+    Ok(HttpResponse::Ok().json(Paginated::paginate(found, method, limit, &req)))
 }
 
-pub async fn show_requests(ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+/// The first page of friend requests, most recent first, which is all the list returned before it was paged.
+pub async fn first_requests(query: web::Query<SearchQuery>, req: HttpRequest, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
     let me = get_identity(&ident)?.userid;
 
-    let friends: Vec<Friend> = {
+    let (found, method, limit) = requests_page(me, SearchMethod::Before, query.into_inner(), &pool.get()?)?;
+
+    Ok(HttpResponse::Ok().json(Paginated::paginate_at(found, method, limit, req.path(), req.query_string())))
+}
+
+fn requests_page(me: i64, method: SearchMethod, query: SearchQuery, db: &PgConnection) -> ValyouResult<(Vec<Friend>, SearchMethod, i64)> {
+    let Page { method, key: (ts, fid), limit } = query.page::<(chrono::NaiveDateTime, i64)>(method)?;
+
+    let found: Vec<Friend> = {
         use crate::views::friend_requests::dsl::*;
 
-        friend_requests
-            .select((friend, username, discriminator, summary, bio, since))
-            .filter(userid.eq(me))
-            .order(friend.asc())
-            .get_results(&pool.get()?)?
+        let columns = (friend, username, discriminator, summary, bio, since, avatar, banner);
+
+        match method {
+            SearchMethod::Before => {
+                friend_requests
+                    .select(columns)
+                    .filter(userid.eq(me))
+                    .filter(since.lt(ts).or(since.eq(ts).and(friend.lt(fid))))
+                    .order((since.desc(), friend.desc()))
+                    .limit(limit)
+                    .get_results(db)?
+            },
+            SearchMethod::After => {
+                friend_requests
+                    .select(columns)
+                    .filter(userid.eq(me))
+                    .filter(since.gt(ts).or(since.eq(ts).and(friend.gt(fid))))
+                    .order((since.asc(), friend.asc()))
+                    .limit(limit)
+                    .get_results(db)?
+            }
+        }
     };
 
-    Ok(HttpResponse::Ok().json(Paginated::paginate(friends, SearchMethod::After)))
+    Ok((found, method, limit))
+}
+
+/// Finds everyone the user is friends with.
+pub fn friend_ids(me: i64, db: &PgConnection) -> ValyouResult<Vec<i64>> {
+    use crate::schema::relations::dsl::*;
+
+    let lower: Vec<i64> = relations
+        .select(user_from)
+        .filter(user_to.eq(me).and(status.eq(RelationStatus::Friends)))
+        .get_results(db)?;
+
+    let upper: Vec<i64> = relations
+        .select(user_to)
+        .filter(user_from.eq(me).and(status.eq(RelationStatus::Friends)))
+        .get_results(db)?;
+
+    Ok(lower.into_iter().chain(upper).collect())
 }
 
 #[inline(always)]
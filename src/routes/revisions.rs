@@ -0,0 +1,83 @@
+use actix_identity::Identity;
+use actix_web::{HttpRequest, HttpResponse, web};
+use diesel::prelude::*;
+
+use crate::errors::{Error, RequestResult, ValyouResult};
+use crate::models::can_see;
+use crate::models::entries::{Revision, RevisionDiff};
+use crate::models::search::{Page, Paginated, SearchMethod, SearchQuery};
+use crate::Pool;
+use crate::routes::account::get_identity;
+
+#[derive(Debug, Deserialize)]
+pub struct DiffQuery {
+    pub from: i64,
+    pub to: i64
+}
+
+pub async fn list(path: web::Path<(i64, i64, SearchMethod)>, query: web::Query<SearchQuery>, req: HttpRequest, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    let (jid, eid, method) = path.into_inner();
+    let Page { method, key, limit } = query.into_inner().page::<i64>(method)?;
+
+    let db = pool.get()?;
+
+    check_access(jid, eid, me, &db)?;
+
+    let found: Vec<Revision> = {
+        use crate::schema::entry_revisions::dsl::*;
+
+        match method {
+            SearchMethod::Before => {
+                entry_revisions
+                    .filter(revisionid.lt(key).and(entry.eq(eid)))
+                    .order(revisionid.desc())
+                    .limit(limit)
+                    .get_results(&db)?
+            },
+            SearchMethod::After => {
+                entry_revisions
+                    .filter(revisionid.gt(key).and(entry.eq(eid)))
+                    .order(revisionid.asc())
+                    .limit(limit)
+                    .get_results(&db)?
+            }
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(Paginated::paginate(found, method, limit, &req)))
+}
+
+pub async fn diff(path: web::Path<(i64, i64)>, query: web::Query<DiffQuery>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    let (jid, eid) = path.into_inner();
+    let DiffQuery { from, to } = query.into_inner();
+
+    let db = pool.get()?;
+
+    check_access(jid, eid, me, &db)?;
+
+    use crate::schema::entry_revisions::dsl::*;
+    let found: Vec<Revision> = entry_revisions
+        .filter(entry.eq(eid).and(revisionid.eq_any(vec![from, to])))
+        .get_results(&db)?;
+
+    let get = |rid: i64| found.iter().find(|r| r.id == rid).cloned().ok_or(Error::NotFound);
+
+    Ok(HttpResponse::Ok().json(RevisionDiff::new(get(from)?, get(to)?)))
+}
+
+/// Revisions follow the visibility of their entry, unless the author has made them private.
+fn check_access(jid: i64, eid: i64, me: i64, db: &PgConnection) -> ValyouResult<()> {
+    use crate::schema::entries::dsl::*;
+
+    entries
+        .select(entryid)
+        .filter(entryid.eq(eid).and(journal.eq(jid)).and(can_see(me, author, journal)))
+        .filter(author.eq(me).or(hidden.eq(false).and(published).and(private_revisions.eq(false))))
+        .first::<i64>(db)?;
+
+    Ok(())
+}
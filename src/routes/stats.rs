@@ -0,0 +1,165 @@
+use actix_identity::Identity;
+use actix_web::{HttpResponse, web};
+use chrono::NaiveDate;
+use diesel::dsl::{count_star, now};
+use diesel::pg::Pg;
+use diesel::prelude::*;
+
+use crate::errors::{Error, RequestResult, ValyouResult};
+use crate::models::{created_between, local_date, word_count};
+use crate::models::stats::{Period, Stats, TagCount, TOP_TAGS, Window};
+use crate::Pool;
+use crate::routes::account::get_identity;
+use crate::routes::profiles::get_timezone;
+
+#[derive(Debug, Deserialize)]
+pub struct StatsQuery {
+    #[serde(default)]
+    pub window: Window,
+    #[serde(default)]
+    pub period: Period
+}
+
+/// Stats over everything the user has written.
+pub async fn own(query: web::Query<StatsQuery>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    let stats = load(me, None, query.into_inner(), &pool.get()?)?;
+
+    Ok(HttpResponse::Ok().json(stats))
+}
+
+/// Stats over one of the user's own journals.
+pub async fn journal(path: web::Path<i64>, query: web::Query<StatsQuery>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let jid = path.into_inner();
+    let me = get_identity(&ident)?.userid;
+
+    let db = pool.get()?;
+
+    let owned: i64 = {
+        use crate::schema::journals::dsl::*;
+
+        journals
+            .filter(journalid.eq(jid).and(owner.eq(me)))
+            .select(count_star())
+            .get_result(&db)?
+    };
+
+    if owned == 0 {
+        return Err(Error::NotFound);
+    }
+
+    let stats = load(me, Some(jid), query.into_inner(), &db)?;
+
+    Ok(HttpResponse::Ok().json(stats))
+}
+
+/// Serves stats from the cache while nothing they were computed from has changed since, and it is still
+/// the same day in the same timezone. Otherwise they are computed again and cached.
+fn load(me: i64, jid: Option<i64>, options: StatsQuery, db: &PgConnection) -> ValyouResult<Stats> {
+    use crate::schema::stats_cache::dsl::*;
+
+    let key = format!("{}:{}:{}", jid.map_or("all".into(), |jid| jid.to_string()), options.window.name(), options.period.name());
+
+    // the version is read in the same snapshot as the entries, so the cache is never ahead of it
+    let (current, tz, today, found, cached) = db.build_transaction()
+        .repeatable_read()
+        .read_only()
+        .run::<_, Error, _>(|| {
+            let current: i64 = {
+                use crate::schema::stats_versions::dsl::*;
+
+                stats_versions
+                    .find(me)
+                    .select(version)
+                    .get_result(db)
+                    .optional()?
+                    .unwrap_or(0)
+            };
+
+            let tz = get_timezone(me, db)?;
+            let today: NaiveDate = diesel::select(local_date(now, tz.as_str())).get_result(db)?;
+
+            let hit: Option<Stats> = stats_cache
+                .find((me, &key))
+                .filter(version.eq(current).and(day.eq(today)).and(timezone.eq(&tz)))
+                .select(stats)
+                .get_result::<serde_json::Value>(db)
+                .optional()?
+                .and_then(|json| serde_json::from_value(json).ok());
+
+            match hit {
+                Some(found) => Ok((current, tz, today, found, true)),
+                None => {
+                    let found = compute(me, jid, &options, &tz, today, db)?;
+                    Ok((current, tz, today, found, false))
+                }
+            }
+        })?;
+
+    if cached {
+        return Ok(found);
+    }
+
+    let json = serde_json::to_value(&found).map_err(|_| Error::InternalServerError)?;
+
+    // a slower request that computed from an older version doesn't get to replace a newer one
+    let updated = diesel::update(stats_cache.find((me, &key)))
+        .filter(version.le(current))
+        .set((version.eq(current), day.eq(today), timezone.eq(&tz), stats.eq(&json), computed.eq(now)))
+        .execute(db)?;
+
+    if updated == 0 {
+        diesel::insert_into(stats_cache)
+            .values(&(userid.eq(me), scope.eq(&key), version.eq(current), day.eq(today), timezone.eq(&tz), stats.eq(&json)))
+            .on_conflict_do_nothing()
+            .execute(db)?;
+    }
+
+    Ok(found)
+}
+
+fn compute(me: i64, jid: Option<i64>, options: &StatsQuery, tz: &str, today: NaiveDate, db: &PgConnection) -> ValyouResult<Stats> {
+    let since = options.window.start(today);
+
+    let written: Vec<(NaiveDate, i32, Option<f64>)> = {
+        use crate::schema::entries::dsl::*;
+
+        written_by(me, jid, since, today, tz)
+            .select((local_date(created, tz), word_count(content), significance))
+            .get_results(db)?
+    };
+
+    let top_tags: Vec<TagCount> = {
+        use crate::schema::entry_tags::dsl::*;
+
+        let tagged = written_by(me, jid, since, today, tz).select(crate::schema::entries::entryid);
+
+        entry_tags
+            .filter(entry.eq_any(tagged))
+            .group_by(tag)
+            .select((tag, count_star()))
+            .order((count_star().desc(), tag.asc()))
+            .limit(TOP_TAGS)
+            .get_results(db)?
+    };
+
+    Ok(Stats::new(written, top_tags, today, options.period))
+}
+
+/// The user's entries, hidden and unpublished ones included, within the window.
+fn written_by<'a>(me: i64, jid: Option<i64>, since: Option<NaiveDate>, today: NaiveDate, tz: &'a str) -> crate::schema::entries::BoxedQuery<'a, Pg> {
+    use crate::schema::entries::dsl::*;
+
+    let mut query = entries.filter(author.eq(me)).into_boxed();
+
+    if let Some(jid) = jid {
+        query = query.filter(journal.eq(jid));
+    }
+
+    if let Some(since) = since {
+        query = query.filter(created_between(created, tz, since, today));
+    }
+
+    query
+}
@@ -0,0 +1,282 @@
+use actix_identity::Identity;
+use actix_web::{HttpRequest, HttpResponse, web};
+use diesel::dsl::count_star;
+use diesel::prelude::*;
+use rand::Rng;
+
+use crate::errors::{Error, RequestResult, ValyouResult};
+use crate::models::search::{Page, Paginated, SearchMethod, SearchQuery};
+use crate::models::webhooks::{check_url, Delivery, MAX_WEBHOOKS, Webhook, WebhookEvent};
+use crate::Pool;
+use crate::routes::account::get_identity;
+use crate::schema::webhooks;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateRequest {
+    pub url: String,
+    pub events: Vec<WebhookEvent>
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EditRequest {
+    pub url: Option<String>,
+    pub events: Option<Vec<WebhookEvent>>,
+    pub active: Option<bool>
+}
+
+#[derive(Debug, AsChangeset)]
+#[table_name = "webhooks"]
+pub struct WebhookChanges {
+    pub url: Option<String>,
+    pub active: Option<bool>
+}
+
+/// The secret is only ever shown here, so receivers have to store it when the webhook is created.
+#[derive(Debug, Serialize)]
+pub struct CreatedWebhook {
+    #[serde(flatten)]
+    pub webhook: Webhook,
+    pub secret: String
+}
+
+pub async fn list(ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    let db = pool.get()?;
+
+    let mut found: Vec<Webhook> = {
+        use self::webhooks::dsl::*;
+
+        webhooks
+            .filter(owner.eq(me))
+            .order(webhookid.asc())
+            .get_results(&db)?
+    };
+
+    for webhook in found.iter_mut() {
+        webhook.events = load_events(webhook.id, &db)?;
+    }
+
+    Ok(HttpResponse::Ok().json(found))
+}
+
+pub async fn create(json: web::Json<CreateRequest>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+    let CreateRequest { url: new_url, events } = json.into_inner();
+
+    check_public(new_url.clone()).await?;
+
+    let db = pool.get()?;
+
+    let existing: i64 = {
+        use self::webhooks::dsl::*;
+
+        webhooks
+            .filter(owner.eq(me))
+            .select(count_star())
+            .get_result(&db)?
+    };
+
+    if existing >= MAX_WEBHOOKS {
+        return Err(Error::BadRequest(format!("users are limited to {} webhooks", MAX_WEBHOOKS)));
+    }
+
+    let new_secret = hex::encode(rand::thread_rng().gen::<[u8; 32]>());
+
+    let mut webhook: Webhook = {
+        use self::webhooks::dsl::*;
+
+        diesel::insert_into(webhooks)
+            .values(&(owner.eq(me), url.eq(new_url), secret.eq(&new_secret)))
+            .get_result(&db)?
+    };
+
+    save_events(webhook.id, &events, &db)?;
+    webhook.events = load_events(webhook.id, &db)?;
+
+    Ok(HttpResponse::Created().json(CreatedWebhook { webhook, secret: new_secret }))
+}
+
+pub async fn edit(path: web::Path<i64>, json: web::Json<EditRequest>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let wid = path.into_inner();
+    let me = get_identity(&ident)?.userid;
+    let EditRequest { url, events, active } = json.into_inner();
+
+    if let Some(url) = &url {
+        check_public(url.clone()).await?;
+    }
+
+    let db = pool.get()?;
+
+    let mut webhook = own_webhook(wid, me, &db)?;
+
+    if url.is_some() || active.is_some() {
+        use self::webhooks::dsl::*;
+
+        webhook = diesel::update(webhooks)
+            .filter(webhookid.eq(wid))
+            .set(&WebhookChanges { url, active })
+            .get_result(&db)?;
+    }
+
+    if let Some(new_events) = events {
+        save_events(wid, &new_events, &db)?;
+    }
+
+    webhook.events = load_events(wid, &db)?;
+
+    Ok(HttpResponse::Ok().json(webhook))
+}
+
+pub async fn delete(path: web::Path<i64>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let wid = path.into_inner();
+    let me = get_identity(&ident)?.userid;
+
+    use self::webhooks::dsl::*;
+    let deleted = diesel::delete(webhooks)
+        .filter(webhookid.eq(wid).and(owner.eq(me)))
+        .execute(&pool.get()?)?;
+
+    if deleted == 0 {
+        return Err(Error::NotFound);
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Queues a ping to the webhook whether or not it is subscribed to anything. Like every other delivery,
+/// it is only sent while the webhook is active.
+pub async fn ping(path: web::Path<i64>, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let wid = path.into_inner();
+    let me = get_identity(&ident)?.userid;
+
+    let db = pool.get()?;
+
+    own_webhook(wid, me, &db)?;
+
+    let delivery = insert_delivery(wid, WebhookEvent::Ping, &json!({ "webhook": format!("{:019}", wid) }), &db)?;
+
+    Ok(HttpResponse::Accepted().json(delivery))
+}
+
+pub async fn deliveries(path: web::Path<(i64, SearchMethod)>, query: web::Query<SearchQuery>, req: HttpRequest, ident: Identity, pool: web::Data<Pool>) -> RequestResult {
+    let me = get_identity(&ident)?.userid;
+
+    let (wid, method) = path.into_inner();
+    let Page { method, key, limit } = query.into_inner().page::<i64>(method)?;
+
+    let db = pool.get()?;
+
+    own_webhook(wid, me, &db)?;
+
+    let found: Vec<Delivery> = {
+        use crate::schema::webhook_deliveries::dsl::*;
+
+        match method {
+            SearchMethod::Before => {
+                webhook_deliveries
+                    .filter(deliveryid.lt(key).and(webhook.eq(wid)))
+                    .order(deliveryid.desc())
+                    .limit(limit)
+                    .get_results(&db)?
+            },
+            SearchMethod::After => {
+                webhook_deliveries
+                    .filter(deliveryid.gt(key).and(webhook.eq(wid)))
+                    .order(deliveryid.asc())
+                    .limit(limit)
+                    .get_results(&db)?
+            }
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(Paginated::paginate(found, method, limit, &req)))
+}
+
+/// Queues a delivery to each of the user's active webhooks that are subscribed to the event.
+pub fn enqueue(user: i64, what: WebhookEvent, data: serde_json::Value, db: &PgConnection) -> ValyouResult<()> {
+    let subscribed: Vec<i64> = {
+        use self::webhooks::dsl::*;
+        use crate::schema::webhook_events;
+
+        webhooks
+            .inner_join(webhook_events::table)
+            .filter(owner.eq(user).and(active).and(webhook_events::event.eq(what)))
+            .select(webhookid)
+            .get_results(db)?
+    };
+
+    for wid in subscribed {
+        insert_delivery(wid, what, &data, db)?;
+    }
+
+    Ok(())
+}
+
+fn insert_delivery(wid: i64, what: WebhookEvent, data: &serde_json::Value, db: &PgConnection) -> ValyouResult<Delivery> {
+    use crate::schema::webhook_deliveries::dsl::*;
+
+    let body = json!({
+        "event": what,
+        "sent": chrono::Utc::now().naive_utc(),
+        "data": data
+    });
+
+    let delivery = diesel::insert_into(webhook_deliveries)
+        .values(&(webhook.eq(wid), event.eq(what), payload.eq(body.to_string())))
+        .get_result(db)?;
+
+    Ok(delivery)
+}
+
+/// Resolving the host blocks, so it is done on the thread pool.
+async fn check_public(url: String) -> ValyouResult<()> {
+    web::block(move || check_url(&url).map_err(Error::BadRequest)).await?;
+
+    Ok(())
+}
+
+fn own_webhook(wid: i64, me: i64, db: &PgConnection) -> ValyouResult<Webhook> {
+    use self::webhooks::dsl::*;
+
+    let found = webhooks
+        .filter(webhookid.eq(wid).and(owner.eq(me)))
+        .get_result(db)?;
+
+    Ok(found)
+}
+
+fn load_events(wid: i64, db: &PgConnection) -> ValyouResult<Vec<WebhookEvent>> {
+    use crate::schema::webhook_events::dsl::*;
+
+    let found = webhook_events
+        .filter(webhook.eq(wid))
+        .select(event)
+        .order(event.asc())
+        .get_results(db)?;
+
+    Ok(found)
+}
+
+/// Replaces the events a webhook is subscribed to.
+fn save_events(wid: i64, events: &[WebhookEvent], db: &PgConnection) -> ValyouResult<()> {
+    use crate::schema::webhook_events::dsl::*;
+
+    diesel::delete(webhook_events)
+        .filter(webhook.eq(wid))
+        .execute(db)?;
+
+    let rows: Vec<_> = events.iter()
+        .filter(|&&e| e != WebhookEvent::Ping)
+        .map(|&e| (webhook.eq(wid), event.eq(e)))
+        .collect();
+
+    if !rows.is_empty() {
+        diesel::insert_into(webhook_events)
+            .values(&rows)
+            .on_conflict_do_nothing()
+            .execute(db)?;
+    }
+
+    Ok(())
+}
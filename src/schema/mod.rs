@@ -1,5 +1,5 @@
 table! {
-    use crate::models::{status::Status, visibility::db::Visibility};
+    use crate::models::{digests::db::DigestFrequency, entries::db::TsVector, notifications::db::NotificationKind, reactions::db::Reaction, status::Status, visibility::db::Visibility, webhooks::db::WebhookEvent};
     use diesel::sql_types::*;
 
     account_age (userid) {
@@ -9,7 +9,7 @@ table! {
 }
 
 table! {
-    use crate::models::{status::Status, visibility::db::Visibility};
+    use crate::models::{digests::db::DigestFrequency, entries::db::TsVector, notifications::db::NotificationKind, reactions::db::Reaction, status::Status, visibility::db::Visibility, webhooks::db::WebhookEvent};
     use diesel::sql_types::*;
 
     accounts (userid) {
@@ -22,7 +22,49 @@ table! {
 }
 
 table! {
-    use crate::models::{status::Status, visibility::db::Visibility};
+    use crate::models::{digests::db::DigestFrequency, entries::db::TsVector, notifications::db::NotificationKind, reactions::db::Reaction, status::Status, visibility::db::Visibility, webhooks::db::WebhookEvent};
+    use diesel::sql_types::*;
+
+    attachments (attachmentid) {
+        attachmentid -> Int8,
+        entry -> Int8,
+        filename -> Varchar,
+        mime -> Varchar,
+        size -> Int4,
+        thumbnail -> Bool,
+        created -> Timestamp,
+    }
+}
+
+table! {
+    use crate::models::{digests::db::DigestFrequency, entries::db::TsVector, notifications::db::NotificationKind, reactions::db::Reaction, status::Status, visibility::db::Visibility, webhooks::db::WebhookEvent};
+    use diesel::sql_types::*;
+
+    comments (commentid) {
+        commentid -> Int8,
+        entry -> Int8,
+        author -> Int8,
+        parent -> Nullable<Int8>,
+        created -> Timestamp,
+        modified -> Nullable<Timestamp>,
+        content -> Varchar,
+    }
+}
+
+table! {
+    use crate::models::{digests::db::DigestFrequency, entries::db::TsVector, notifications::db::NotificationKind, reactions::db::Reaction, status::Status, visibility::db::Visibility, webhooks::db::WebhookEvent};
+    use diesel::sql_types::*;
+
+    digest_subscriptions (userid) {
+        userid -> Int8,
+        frequency -> DigestFrequency,
+        created -> Timestamp,
+        last_sent -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    use crate::models::{digests::db::DigestFrequency, entries::db::TsVector, notifications::db::NotificationKind, reactions::db::Reaction, status::Status, visibility::db::Visibility, webhooks::db::WebhookEvent};
     use diesel::sql_types::*;
 
     entries (entryid) {
@@ -35,11 +77,50 @@ table! {
         content -> Varchar,
         significance -> Nullable<Float8>,
         hidden -> Bool,
+        private_revisions -> Bool,
+        published -> Bool,
+        publish_at -> Nullable<Timestamp>,
+        html -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    use crate::models::{digests::db::DigestFrequency, entries::db::TsVector, notifications::db::NotificationKind, reactions::db::Reaction, status::Status, visibility::db::Visibility, webhooks::db::WebhookEvent};
+    use diesel::sql_types::*;
+
+    entry_mentions (entry, start) {
+        entry -> Int8,
+        userid -> Int8,
+        start -> Int4,
+        length -> Int4,
+    }
+}
+
+table! {
+    use crate::models::{digests::db::DigestFrequency, entries::db::TsVector, notifications::db::NotificationKind, reactions::db::Reaction, status::Status, visibility::db::Visibility, webhooks::db::WebhookEvent};
+    use diesel::sql_types::*;
+
+    entry_revisions (revisionid) {
+        revisionid -> Int8,
+        entry -> Int8,
+        created -> Timestamp,
+        content -> Varchar,
+        significance -> Nullable<Float8>,
     }
 }
 
 table! {
-    use crate::models::{status::Status, visibility::db::Visibility};
+    use crate::models::{digests::db::DigestFrequency, entries::db::TsVector, notifications::db::NotificationKind, reactions::db::Reaction, status::Status, visibility::db::Visibility, webhooks::db::WebhookEvent};
+    use diesel::sql_types::*;
+
+    entry_search (entry) {
+        entry -> Int8,
+        document -> TsVector,
+    }
+}
+
+table! {
+    use crate::models::{digests::db::DigestFrequency, entries::db::TsVector, notifications::db::NotificationKind, reactions::db::Reaction, status::Status, visibility::db::Visibility, webhooks::db::WebhookEvent};
     use diesel::sql_types::*;
 
     entry_tags (entry, tag) {
@@ -49,7 +130,27 @@ table! {
 }
 
 table! {
-    use crate::models::{status::Status, visibility::db::Visibility};
+    use crate::models::{digests::db::DigestFrequency, entries::db::TsVector, notifications::db::NotificationKind, reactions::db::Reaction, status::Status, visibility::db::Visibility, webhooks::db::WebhookEvent};
+    use diesel::sql_types::*;
+
+    feed_invalidations (userid) {
+        userid -> Int8,
+        queued -> Timestamp,
+    }
+}
+
+table! {
+    use crate::models::{digests::db::DigestFrequency, entries::db::TsVector, notifications::db::NotificationKind, reactions::db::Reaction, status::Status, visibility::db::Visibility, webhooks::db::WebhookEvent};
+    use diesel::sql_types::*;
+
+    feed_items (userid, entry) {
+        userid -> Int8,
+        entry -> Int8,
+    }
+}
+
+table! {
+    use crate::models::{digests::db::DigestFrequency, entries::db::TsVector, notifications::db::NotificationKind, reactions::db::Reaction, status::Status, visibility::db::Visibility, webhooks::db::WebhookEvent};
     use diesel::sql_types::*;
 
     journals (journalid) {
@@ -61,11 +162,54 @@ table! {
         description -> Nullable<Varchar>,
         visibility -> Visibility,
         color -> Int4,
+        edit_window -> Nullable<Int4>,
+        comments_enabled -> Bool,
     }
 }
 
 table! {
-    use crate::models::{status::Status, visibility::db::Visibility};
+    use crate::models::{digests::db::DigestFrequency, entries::db::TsVector, notifications::db::NotificationKind, reactions::db::Reaction, status::Status, visibility::db::Visibility, webhooks::db::WebhookEvent};
+    use diesel::sql_types::*;
+
+    messages (messageid) {
+        messageid -> Int8,
+        sender -> Int8,
+        recipient -> Int8,
+        created -> Timestamp,
+        content -> Varchar,
+        read -> Bool,
+    }
+}
+
+table! {
+    use crate::models::{digests::db::DigestFrequency, entries::db::TsVector, notifications::db::NotificationKind, reactions::db::Reaction, status::Status, visibility::db::Visibility, webhooks::db::WebhookEvent};
+    use diesel::sql_types::*;
+
+    notification_preferences (userid, kind) {
+        userid -> Int8,
+        kind -> NotificationKind,
+        enabled -> Bool,
+    }
+}
+
+table! {
+    use crate::models::{digests::db::DigestFrequency, entries::db::TsVector, notifications::db::NotificationKind, reactions::db::Reaction, status::Status, visibility::db::Visibility, webhooks::db::WebhookEvent};
+    use diesel::sql_types::*;
+
+    notifications (notificationid) {
+        notificationid -> Int8,
+        userid -> Int8,
+        kind -> NotificationKind,
+        actor -> Int8,
+        entry -> Nullable<Int8>,
+        comment -> Nullable<Int8>,
+        created -> Timestamp,
+        read -> Bool,
+    }
+}
+
+table! {
+    use crate::models::{digests::db::DigestFrequency, entries::db::TsVector, notifications::db::NotificationKind, reactions::db::Reaction, status::Status, visibility::db::Visibility, webhooks::db::WebhookEvent};
     use diesel::sql_types::*;
 
     profiles (userid) {
@@ -74,11 +218,26 @@ table! {
         summary -> Nullable<Varchar>,
         bio -> Nullable<Varchar>,
         modified -> Nullable<Timestamp>,
+        avatar -> Nullable<Int8>,
+        banner -> Nullable<Int8>,
+        timezone -> Varchar,
     }
 }
 
 table! {
-    use crate::models::{status::Status, visibility::db::Visibility};
+    use crate::models::{digests::db::DigestFrequency, entries::db::TsVector, notifications::db::NotificationKind, reactions::db::Reaction, status::Status, visibility::db::Visibility, webhooks::db::WebhookEvent};
+    use diesel::sql_types::*;
+
+    reactions (entry, userid, reaction) {
+        entry -> Int8,
+        userid -> Int8,
+        reaction -> Reaction,
+        created -> Timestamp,
+    }
+}
+
+table! {
+    use crate::models::{digests::db::DigestFrequency, entries::db::TsVector, notifications::db::NotificationKind, reactions::db::Reaction, status::Status, visibility::db::Visibility, webhooks::db::WebhookEvent};
     use diesel::sql_types::*;
 
     relations (user_from, user_to) {
@@ -90,7 +249,53 @@ table! {
 }
 
 table! {
-    use crate::models::{status::Status, visibility::db::Visibility};
+    use crate::models::{digests::db::DigestFrequency, entries::db::TsVector, notifications::db::NotificationKind, reactions::db::Reaction, status::Status, visibility::db::Visibility, webhooks::db::WebhookEvent};
+    use diesel::sql_types::*;
+
+    removed_attachments (attachmentid) {
+        attachmentid -> Int8,
+        thumbnail -> Bool,
+    }
+}
+
+table! {
+    use crate::models::{digests::db::DigestFrequency, entries::db::TsVector, notifications::db::NotificationKind, reactions::db::Reaction, status::Status, visibility::db::Visibility, webhooks::db::WebhookEvent};
+    use diesel::sql_types::*;
+
+    removed_profile_images (userid) {
+        userid -> Int8,
+        avatar -> Nullable<Int8>,
+        banner -> Nullable<Int8>,
+    }
+}
+
+table! {
+    use crate::models::{digests::db::DigestFrequency, entries::db::TsVector, notifications::db::NotificationKind, reactions::db::Reaction, status::Status, visibility::db::Visibility, webhooks::db::WebhookEvent};
+    use diesel::sql_types::*;
+
+    stats_cache (userid, scope) {
+        userid -> Int8,
+        scope -> Varchar,
+        version -> Int8,
+        day -> Date,
+        timezone -> Varchar,
+        stats -> Jsonb,
+        computed -> Timestamp,
+    }
+}
+
+table! {
+    use crate::models::{digests::db::DigestFrequency, entries::db::TsVector, notifications::db::NotificationKind, reactions::db::Reaction, status::Status, visibility::db::Visibility, webhooks::db::WebhookEvent};
+    use diesel::sql_types::*;
+
+    stats_versions (userid) {
+        userid -> Int8,
+        version -> Int8,
+    }
+}
+
+table! {
+    use crate::models::{digests::db::DigestFrequency, entries::db::TsVector, notifications::db::NotificationKind, reactions::db::Reaction, status::Status, visibility::db::Visibility, webhooks::db::WebhookEvent};
     use diesel::sql_types::*;
 
     usernames (userid) {
@@ -101,21 +306,103 @@ table! {
     }
 }
 
+table! {
+    use crate::models::{digests::db::DigestFrequency, entries::db::TsVector, notifications::db::NotificationKind, reactions::db::Reaction, status::Status, visibility::db::Visibility, webhooks::db::WebhookEvent};
+    use diesel::sql_types::*;
+
+    webhook_deliveries (deliveryid) {
+        deliveryid -> Int8,
+        webhook -> Int8,
+        event -> WebhookEvent,
+        payload -> Varchar,
+        created -> Timestamp,
+        attempts -> Int4,
+        next_attempt -> Nullable<Timestamp>,
+        delivered -> Nullable<Timestamp>,
+        status -> Nullable<Int4>,
+        error -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    use crate::models::{digests::db::DigestFrequency, entries::db::TsVector, notifications::db::NotificationKind, reactions::db::Reaction, status::Status, visibility::db::Visibility, webhooks::db::WebhookEvent};
+    use diesel::sql_types::*;
+
+    webhook_events (webhook, event) {
+        webhook -> Int8,
+        event -> WebhookEvent,
+    }
+}
+
+table! {
+    use crate::models::{digests::db::DigestFrequency, entries::db::TsVector, notifications::db::NotificationKind, reactions::db::Reaction, status::Status, visibility::db::Visibility, webhooks::db::WebhookEvent};
+    use diesel::sql_types::*;
+
+    webhooks (webhookid) {
+        webhookid -> Int8,
+        owner -> Int8,
+        url -> Varchar,
+        secret -> Varchar,
+        active -> Bool,
+        created -> Timestamp,
+    }
+}
+
 joinable!(account_age -> accounts (userid));
+joinable!(attachments -> entries (entry));
+joinable!(comments -> entries (entry));
+joinable!(comments -> profiles (author));
+joinable!(digest_subscriptions -> accounts (userid));
 joinable!(entries -> journals (journal));
 joinable!(entries -> profiles (author));
+joinable!(entry_mentions -> entries (entry));
+joinable!(entry_mentions -> profiles (userid));
+joinable!(entry_revisions -> entries (entry));
+joinable!(entry_search -> entries (entry));
 joinable!(entry_tags -> entries (entry));
+joinable!(feed_invalidations -> profiles (userid));
+joinable!(feed_items -> entries (entry));
+joinable!(feed_items -> profiles (userid));
 joinable!(journals -> profiles (owner));
+joinable!(notification_preferences -> profiles (userid));
+joinable!(notifications -> comments (comment));
+joinable!(notifications -> entries (entry));
 joinable!(profiles -> accounts (userid));
+joinable!(reactions -> entries (entry));
+joinable!(reactions -> profiles (userid));
+joinable!(stats_cache -> profiles (userid));
+joinable!(stats_versions -> profiles (userid));
 joinable!(usernames -> profiles (userid));
+joinable!(webhook_deliveries -> webhooks (webhook));
+joinable!(webhook_events -> webhooks (webhook));
+joinable!(webhooks -> profiles (owner));
 
 allow_tables_to_appear_in_same_query!(
     account_age,
     accounts,
+    attachments,
+    comments,
+    digest_subscriptions,
     entries,
+    entry_mentions,
+    entry_revisions,
+    entry_search,
     entry_tags,
+    feed_invalidations,
+    feed_items,
     journals,
+    messages,
+    notification_preferences,
+    notifications,
     profiles,
+    reactions,
     relations,
+    removed_attachments,
+    removed_profile_images,
+    stats_cache,
+    stats_versions,
     usernames,
+    webhook_deliveries,
+    webhook_events,
+    webhooks,
 );
@@ -0,0 +1,69 @@
+use std::io::Cursor;
+
+use image::{DynamicImage, ImageFormat, ImageOutputFormat};
+use image::imageops::FilterType;
+use image::io::Reader;
+
+use crate::errors::{Error, ValyouResult};
+
+/// The most pixels an upload can have, checked from its header before it is decoded so a small file
+/// can't claim dimensions that would take gigabytes to hold.
+pub const MAX_PIXELS: u64 = 40_000_000;
+
+/// The image formats accepted for upload, by MIME type.
+pub fn format_for(mime: &str) -> Option<ImageFormat> {
+    match mime {
+        "image/png" => Some(ImageFormat::Png),
+        "image/jpeg" => Some(ImageFormat::Jpeg),
+        "image/gif" => Some(ImageFormat::Gif),
+        "image/webp" => Some(ImageFormat::WebP),
+        _ => None
+    }
+}
+
+/// Decodes an upload, making sure its contents really are in the format it claims to be.
+pub fn load(data: &[u8], format: ImageFormat) -> ValyouResult<DynamicImage> {
+    match image::guess_format(data) {
+        Ok(guessed) if guessed == format => {
+            let unreadable = |_| Error::BadRequest("could not read image".into());
+
+            let (width, height) = Reader::with_format(Cursor::new(data), format)
+                .into_dimensions()
+                .map_err(unreadable)?;
+
+            if width as u64 * height as u64 > MAX_PIXELS {
+                return Err(Error::BadRequest(format!("images are limited to {} pixels", MAX_PIXELS)));
+            }
+
+            image::load_from_memory_with_format(data, format).map_err(unreadable)
+        },
+        _ => Err(Error::BadRequest("image does not match its content type".into()))
+    }
+}
+
+/// Decodes an upload that has to be an image.
+pub fn decode(data: &[u8], mime: &str) -> ValyouResult<DynamicImage> {
+    let format = format_for(mime)
+        .ok_or_else(|| Error::BadRequest(format!("expected an image, not {}", mime)))?;
+
+    load(data, format)
+}
+
+/// Scales and crops an image to exactly `width` by `height`.
+pub fn cover(image: &DynamicImage, width: u32, height: u32) -> ValyouResult<Vec<u8>> {
+    encode_png(&image.resize_to_fill(width, height, FilterType::Lanczos3))
+}
+
+/// Scales an image down to fit within a `size` by `size` square, keeping its aspect ratio.
+pub fn thumbnail(image: &DynamicImage, size: u32) -> ValyouResult<Vec<u8>> {
+    encode_png(&image.thumbnail(size, size))
+}
+
+pub fn encode_png(image: &DynamicImage) -> ValyouResult<Vec<u8>> {
+    let mut out = Vec::new();
+
+    image.write_to(&mut out, ImageOutputFormat::Png)
+        .map_err(|_| Error::InternalServerError)?;
+
+    Ok(out)
+}
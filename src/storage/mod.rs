@@ -0,0 +1,91 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use actix_web::web;
+
+use crate::errors::{Error, ValyouResult};
+
+pub mod images;
+
+/// The blob store the server was configured with, shared between workers.
+pub type Storage = Arc<dyn BlobStore>;
+
+/// Somewhere to keep uploaded files, addressed by `/` separated keys generated by the server.
+pub trait BlobStore: Send + Sync {
+    fn put(&self, key: &str, data: &[u8]) -> ValyouResult<()>;
+
+    fn get(&self, key: &str) -> ValyouResult<Vec<u8>>;
+
+    /// Deleting a key that doesn't exist is not an error.
+    fn delete(&self, key: &str) -> ValyouResult<()>;
+}
+
+/// Reads a stored file on the thread pool.
+pub async fn read(storage: &Storage, key: String) -> ValyouResult<Vec<u8>> {
+    let storage = storage.clone();
+
+    Ok(web::block(move || storage.get(&key)).await?)
+}
+
+/// Picks the storage backend from the environment, currently always the local filesystem.
+pub fn from_env() -> Storage {
+    let root = dotenv::var("STORAGE_PATH").unwrap_or_else(|_| "storage".into());
+
+    Arc::new(LocalStore::new(root))
+}
+
+/// Stores blobs as files under a root directory.
+pub struct LocalStore {
+    root: PathBuf
+}
+
+impl LocalStore {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        LocalStore { root: root.into() }
+    }
+
+    fn path(&self, key: &str) -> ValyouResult<PathBuf> {
+        let key = Path::new(key);
+
+        let relative = key.components().all(|c| match c {
+            Component::Normal(_) => true,
+            _ => false
+        });
+
+        if relative {
+            Ok(self.root.join(key))
+        } else {
+            Err(Error::InternalServerError)
+        }
+    }
+}
+
+impl BlobStore for LocalStore {
+    fn put(&self, key: &str, data: &[u8]) -> ValyouResult<()> {
+        let path = self.path(key)?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, data)?;
+
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> ValyouResult<Vec<u8>> {
+        fs::read(self.path(key)?).map_err(|e| match e.kind() {
+            ErrorKind::NotFound => Error::NotFound,
+            _ => Error::from(e),
+        })
+    }
+
+    fn delete(&self, key: &str) -> ValyouResult<()> {
+        match fs::remove_file(self.path(key)?) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(())
+        }
+    }
+}
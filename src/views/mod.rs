@@ -1,3 +1,4 @@
+use crate::schema::{entry_tags, feed_items};
 
 table! {
     new_account (email) {
@@ -14,6 +15,8 @@ table! {
         discriminator -> SmallInt,
         summary -> Nullable<Varchar>,
         bio -> Nullable<Varchar>,
+        avatar -> Nullable<Int8>,
+        banner -> Nullable<Int8>,
     }
 }
 
@@ -31,6 +34,9 @@ table! {
         created -> Timestamp,
         modified -> Nullable<Timestamp>,
         username_modified -> Nullable<Timestamp>,
+        avatar -> Nullable<Int8>,
+        banner -> Nullable<Int8>,
+        timezone -> Varchar,
     }
 }
 
@@ -43,6 +49,8 @@ table! {
         summary -> Nullable<Varchar>,
         bio -> Nullable<Varchar>,
         since -> Timestamp,
+        avatar -> Nullable<Int8>,
+        banner -> Nullable<Int8>,
     }
 }
 
@@ -55,6 +63,8 @@ table! {
         summary -> Nullable<Varchar>,
         bio -> Nullable<Varchar>,
         since -> Timestamp,
+        avatar -> Nullable<Int8>,
+        banner -> Nullable<Int8>,
     }
 }
 
@@ -69,6 +79,10 @@ table! {
         content -> Varchar,
         significance -> Nullable<Float8>,
         tags -> Varchar,
+        edit_time_left -> Nullable<Int8>,
+        published -> Bool,
+        publish_at -> Nullable<Timestamp>,
+        html -> Nullable<Varchar>,
     }
 }
 
@@ -83,5 +97,43 @@ table! {
         content -> Varchar,
         significance -> Nullable<Float8>,
         tags -> Varchar,
+        edit_time_left -> Nullable<Int8>,
+        published -> Bool,
+        publish_at -> Nullable<Timestamp>,
+        html -> Nullable<Varchar>,
     }
-}
\ No newline at end of file
+}
+
+table! {
+    use crate::models::notifications::db::NotificationKind;
+    use diesel::sql_types::*;
+
+    inbox (notificationid) {
+        notificationid -> Int8,
+        userid -> Int8,
+        kind -> NotificationKind,
+        actor -> Int8,
+        entry -> Nullable<Int8>,
+        journal -> Nullable<Int8>,
+        comment -> Nullable<Int8>,
+        created -> Timestamp,
+        read -> Bool,
+    }
+}
+
+table! {
+    conversations (userid, other) {
+        userid -> Int8,
+        other -> Int8,
+        username -> Varchar,
+        discriminator -> SmallInt,
+        last_message -> Int8,
+        last_sender -> Int8,
+        created -> Timestamp,
+        content -> Varchar,
+        unread -> Int8,
+    }
+}
+
+allow_tables_to_appear_in_same_query!(entry_tags, visible_entries);
+allow_tables_to_appear_in_same_query!(feed_items, visible_entries);